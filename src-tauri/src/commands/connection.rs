@@ -15,24 +15,34 @@ use crate::state::AppState;
 ///
 /// Creates a new database connection and stores it in AppState.
 /// Frontend can use the returned ID to execute queries~
+///
+/// Pass `saved_connection_id` when connecting to a saved connection, and
+/// the queries run on it land in its history automatically (unless its
+/// `record_history` is off)! 📜
 #[tauri::command]
 pub async fn connect(
     state: State<'_, AppState>,
     config: ConnectionConfig,
+    saved_connection_id: Option<String>,
 ) -> Result<String, AppError> {
-    state.connect(&config).await
+    state.connect(&config, saved_connection_id.as_deref()).await
 }
 
 /// Close a database connection gracefully! 🌸
 ///
 /// Removes the connection from AppState and closes all resources.
 /// Always disconnect when you're done to prevent resource leaks!
+///
+/// If editor sessions still have transactions open, this fails with an
+/// "open transaction(s)" error unless `force` is set - ask the user, then
+/// retry with `force: true` to roll them back.
 #[tauri::command]
 pub async fn disconnect(
     state: State<'_, AppState>,
     connection_id: String,
+    force: Option<bool>,
 ) -> Result<(), AppError> {
-    state.disconnect(&connection_id).await
+    state.disconnect(&connection_id, force.unwrap_or(false)).await
 }
 
 /// Test if a connection configuration is valid! ✨💫
@@ -43,6 +53,7 @@ pub async fn disconnect(
 pub async fn test_connection(config: ConnectionConfig) -> Result<bool, AppError> {
     use crate::db::mysql::MySqlConnector;
     use crate::db::postgres::PostgresConnector;
+    use crate::db::sqlite::SqliteConnector;
     use crate::db::DatabaseDriver;
 
     match config.driver {
@@ -56,5 +67,10 @@ pub async fn test_connection(config: ConnectionConfig) -> Result<bool, AppError>
             connector.close().await?;
             Ok(true)
         }
+        DatabaseDriver::SQLite => {
+            let connector = SqliteConnector::connect(&config).await?;
+            connector.close().await?;
+            Ok(true)
+        }
     }
 }
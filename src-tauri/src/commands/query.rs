@@ -1,19 +1,310 @@
+use std::time::Instant;
+use tauri::ipc::Channel;
 use tauri::State;
 
-use crate::db::connector::QueryResult;
+use crate::db::connector::{QueryLimits, QueryResult, TransactionState};
+use crate::db::params::{self, QueryParams};
+use crate::db::script;
+use crate::db::stream::{ResultPage, StreamEvent, StreamOptions, StreamSummary, DEFAULT_PAGE_SIZE};
 use crate::error::AppError;
-use crate::state::AppState;
+use crate::state::{AppState, QueryRun};
 
+/// Run a query, optionally with bound parameters.
+///
+/// `params` is either a list bound in order to `?` / `$n` placeholders, or
+/// an object bound by name to `:name` placeholders, e.g.
+/// `{ "user_id": { "type": "int", "value": 7 } }`.
+///
+/// `timeout_ms` and `max_rows` override the connection's defaults for this
+/// call; the result's `truncated` flag says if the row limit cut it short.
+///
+/// The query is recorded in history, successful or not.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_query(
     state: State<'_, AppState>,
     connection_id: String,
     query: String,
     database: Option<String>,
     context: Option<String>,
+    session_id: Option<String>,
+    params: Option<QueryParams>,
+    timeout_ms: Option<u64>,
+    max_rows: Option<u64>,
 ) -> Result<QueryResult, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    let (resolved, params) = params::resolve(&query, params, connector.driver())?;
+    let started = Instant::now();
+    let result = connector
+        .execute_with_params(
+            &resolved,
+            &params,
+            database.as_deref(),
+            context.as_deref(),
+            session_id.as_deref(),
+            &QueryLimits { timeout_ms, max_rows },
+        )
+        .await;
+
+    let run = match &result {
+        Ok(result) => QueryRun::from_result(&query, result),
+        Err(e) => QueryRun::failed(&query, started, e),
+    };
+    state
+        .record_history(&connection_id, connector.as_ref(), database.as_deref(), context.as_deref(), &[run])
+        .await;
+    result
+}
+
+/// Run a multi-statement script, one result per statement.
+///
+/// Statements are split dialect-aware and run in order on one session.
+/// Failed statements come back with `error` set; `stop_on_error` (default
+/// `true`) ends the script at the first failure. Each statement that ran is
+/// recorded in history.
+#[tauri::command]
+pub async fn execute_script(
+    state: State<'_, AppState>,
+    connection_id: String,
+    script: String,
+    database: Option<String>,
+    context: Option<String>,
+    session_id: Option<String>,
+    stop_on_error: Option<bool>,
+) -> Result<Vec<QueryResult>, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    let started = Instant::now();
+    let result = script::execute_script(
+        connector.as_ref(),
+        &script,
+        database.as_deref(),
+        context.as_deref(),
+        session_id.as_deref(),
+        stop_on_error.unwrap_or(true),
+    )
+    .await;
+
+    let runs: Vec<QueryRun> = match &result {
+        Ok(results) => results
+            .iter()
+            .map(|r| QueryRun::from_result(r.original_query.as_deref().unwrap_or(&script), r))
+            .collect(),
+        Err(e) => vec![QueryRun::failed(&script, started, e)],
+    };
+    state
+        .record_history(&connection_id, connector.as_ref(), database.as_deref(), context.as_deref(), &runs)
+        .await;
+    result
+}
+
+/// Run a query and stream its rows over `on_event` in batches.
+///
+/// The column info arrives first, then batches of `batch_size` rows (default
+/// 500) until the result ends or `max_rows` rows were sent (default: the
+/// connection's row limit, or 100,000 without one). `timeout_ms` overrides
+/// the connection's timeout, like in `execute_query`.
+/// The returned summary says whether the result was cut short. The query is
+/// recorded in history.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_query_stream(
+    state: State<'_, AppState>,
+    connection_id: String,
+    query: String,
+    database: Option<String>,
+    context: Option<String>,
+    session_id: Option<String>,
+    batch_size: Option<usize>,
+    max_rows: Option<usize>,
+    timeout_ms: Option<u64>,
+    on_event: Channel<StreamEvent>,
+) -> Result<StreamSummary, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    let defaults = StreamOptions::default();
+    let connection_max_rows = connector.limits().max_rows.map(|n| usize::try_from(n).unwrap_or(usize::MAX));
+    let options = StreamOptions {
+        batch_size: batch_size.unwrap_or(defaults.batch_size),
+        max_rows: max_rows.or(connection_max_rows).unwrap_or(defaults.max_rows),
+    };
+    let sink = move |event: StreamEvent| {
+        on_event
+            .send(event)
+            .map_err(|e| AppError::Validation(format!("Failed to send rows to the editor: {}", e)))
+    };
+
+    let started = Instant::now();
+    let result = connector
+        .execute_streaming(
+            &query,
+            database.as_deref(),
+            context.as_deref(),
+            session_id.as_deref(),
+            &QueryLimits { timeout_ms, max_rows: None },
+            &options,
+            &sink,
+        )
+        .await;
+
+    let run = match &result {
+        Ok(summary) => QueryRun {
+            query: &query,
+            execution_time_ms: summary.execution_time_ms,
+            row_count: Some(if summary.row_count > 0 { summary.row_count } else { summary.affected_rows }),
+            error: None,
+        },
+        Err(e) => QueryRun::failed(&query, started, e),
+    };
+    state
+        .record_history(&connection_id, connector.as_ref(), database.as_deref(), context.as_deref(), &[run])
+        .await;
+    result
+}
+
+/// Open a cursor for a query in an editor session and return its first page.
+///
+/// Any cursor the session already had is closed. `page_size` defaults to 500.
+/// The query is recorded in history (without a row count - it's read in pages).
+/// Cursors are exempt from the connection's timeout, since they stay open
+/// while the user pages - `cancel_query` stops a slow one.
+#[tauri::command]
+pub async fn open_cursor(
+    state: State<'_, AppState>,
+    connection_id: String,
+    query: String,
+    database: Option<String>,
+    context: Option<String>,
+    session_id: String,
+    page_size: Option<usize>,
+) -> Result<ResultPage, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    let started = Instant::now();
+    let result = connector
+        .open_cursor(
+            &query,
+            database.as_deref(),
+            context.as_deref(),
+            &session_id,
+            page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        )
+        .await;
+
+    let run = match &result {
+        Ok(_) => QueryRun {
+            query: &query,
+            execution_time_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            error: None,
+        },
+        Err(e) => QueryRun::failed(&query, started, e),
+    };
+    state
+        .record_history(&connection_id, connector.as_ref(), database.as_deref(), context.as_deref(), &[run])
+        .await;
+    result
+}
+
+/// Read the next page of the session's cursor.
+///
+/// The cursor is closed automatically after the page with `has_more: false`.
+#[tauri::command]
+pub async fn fetch_next_page(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+    page_size: Option<usize>,
+) -> Result<ResultPage, AppError> {
     let connector = state.get_connection(&connection_id).await?;
     connector
-        .execute_with_context(&query, database.as_deref(), context.as_deref())
+        .fetch_next_page(&session_id, page_size.unwrap_or(DEFAULT_PAGE_SIZE))
         .await
 }
+
+/// Close the session's cursor before reading all of it.
+#[tauri::command]
+pub async fn close_cursor(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<(), AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.close_cursor(&session_id).await
+}
+
+/// Cancel the query running for an editor session.
+///
+/// Returns `false` when the session has no query in flight. The cancelled
+/// `execute_query` call rejects with a "Query cancelled" error.
+#[tauri::command]
+pub async fn cancel_query(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<bool, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.cancel_query(&session_id).await
+}
+
+/// Close an editor session and release its pinned connection.
+///
+/// Call this when a tab closes. A query still running in the session is
+/// cancelled first; unknown sessions are ignored.
+#[tauri::command]
+pub async fn close_session(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<(), AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.close_session(&session_id).await
+}
+
+/// Open a transaction in an editor session.
+///
+/// Statements the session runs afterwards stay uncommitted until
+/// `commit_transaction` or `rollback_transaction`.
+#[tauri::command]
+pub async fn begin_transaction(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+    database: Option<String>,
+) -> Result<TransactionState, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.begin_transaction(&session_id, database.as_deref()).await
+}
+
+/// Commit the transaction of an editor session.
+#[tauri::command]
+pub async fn commit_transaction(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<TransactionState, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.commit_transaction(&session_id).await
+}
+
+/// Roll back the transaction of an editor session.
+#[tauri::command]
+pub async fn rollback_transaction(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<TransactionState, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    connector.rollback_transaction(&session_id).await
+}
+
+/// Transaction state of an editor session.
+///
+/// Useful after a failed query, which carries no `QueryResult` to read the
+/// state from.
+#[tauri::command]
+pub async fn get_transaction_state(
+    state: State<'_, AppState>,
+    connection_id: String,
+    session_id: String,
+) -> Result<TransactionState, AppError> {
+    let connector = state.get_connection(&connection_id).await?;
+    Ok(connector.transaction_state(&session_id))
+}
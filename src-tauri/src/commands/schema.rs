@@ -1,7 +1,9 @@
 use log::{error, info};
 use tauri::State;
 
-use crate::db::connector::{ColumnDetail, SchemaInfo, TableInfo};
+use crate::db::connector::{
+    ColumnDetail, ConstraintInfo, IndexInfo, SchemaInfo, SchemaObject, SchemaObjectKind, TableInfo, TableStats,
+};
 use crate::error::AppError;
 use crate::state::AppState;
 
@@ -123,3 +125,146 @@ pub async fn get_columns(
 
     result
 }
+
+#[tauri::command]
+pub async fn get_indexes(
+    state: State<'_, AppState>,
+    connection_id: String,
+    database: String,
+    schema: String,
+    table: String,
+) -> Result<Vec<IndexInfo>, AppError> {
+    info!("[Command] get_indexes called with connection_id: {}, database: '{}', schema: '{}', table: '{}'",
+        connection_id, database, schema, table);
+
+    let connector = state.get_connection(&connection_id).await.map_err(|e| {
+        error!("[Command] get_indexes - failed to get connection: {:?}", e);
+        e
+    })?;
+
+    let result = connector.get_indexes(&database, &schema, &table).await;
+
+    match &result {
+        Ok(indexes) => {
+            info!("[Command] get_indexes returning {} indexes for {}.{}.{}: {:?}",
+                indexes.len(), database, schema, table, indexes.iter().map(|i| &i.name).collect::<Vec<_>>());
+        }
+        Err(e) => {
+            error!("[Command] get_indexes failed for {}.{}.{}: {:?}", database, schema, table, e);
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn get_constraints(
+    state: State<'_, AppState>,
+    connection_id: String,
+    database: String,
+    schema: String,
+    table: String,
+) -> Result<Vec<ConstraintInfo>, AppError> {
+    info!("[Command] get_constraints called with connection_id: {}, database: '{}', schema: '{}', table: '{}'",
+        connection_id, database, schema, table);
+
+    let connector = state.get_connection(&connection_id).await.map_err(|e| {
+        error!("[Command] get_constraints - failed to get connection: {:?}", e);
+        e
+    })?;
+
+    let result = connector.get_constraints(&database, &schema, &table).await;
+
+    match &result {
+        Ok(constraints) => {
+            info!("[Command] get_constraints returning {} constraints for {}.{}.{}: {:?}",
+                constraints.len(), database, schema, table, constraints.iter().map(|c| &c.name).collect::<Vec<_>>());
+        }
+        Err(e) => {
+            error!("[Command] get_constraints failed for {}.{}.{}: {:?}", database, schema, table, e);
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn get_object_ddl(
+    state: State<'_, AppState>,
+    connection_id: String,
+    database: String,
+    schema: String,
+    name: String,
+) -> Result<String, AppError> {
+    info!("[Command] get_object_ddl called with connection_id: {}, database: '{}', schema: '{}', name: '{}'",
+        connection_id, database, schema, name);
+
+    let connector = state.get_connection(&connection_id).await.map_err(|e| {
+        error!("[Command] get_object_ddl - failed to get connection: {:?}", e);
+        e
+    })?;
+
+    let result = connector.get_object_ddl(&database, &schema, &name).await;
+
+    if let Err(e) = &result {
+        error!("[Command] get_object_ddl failed for {}.{}.{}: {:?}", database, schema, name, e);
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn list_objects(
+    state: State<'_, AppState>,
+    connection_id: String,
+    database: String,
+    schema: String,
+    kind: SchemaObjectKind,
+) -> Result<Vec<SchemaObject>, AppError> {
+    info!("[Command] list_objects called with connection_id: {}, database: '{}', schema: '{}', kind: {:?}",
+        connection_id, database, schema, kind);
+
+    let connector = state.get_connection(&connection_id).await.map_err(|e| {
+        error!("[Command] list_objects - failed to get connection: {:?}", e);
+        e
+    })?;
+
+    let result = connector.list_objects(&database, &schema, kind).await;
+
+    match &result {
+        Ok(objects) => {
+            info!("[Command] list_objects returning {} {:?} objects for {}.{}: {:?}",
+                objects.len(), kind, database, schema, objects.iter().map(|o| &o.name).collect::<Vec<_>>());
+        }
+        Err(e) => {
+            error!("[Command] list_objects failed for {}.{} ({:?}): {:?}", database, schema, kind, e);
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn get_table_stats(
+    state: State<'_, AppState>,
+    connection_id: String,
+    database: String,
+    schema: String,
+    table: String,
+) -> Result<TableStats, AppError> {
+    info!("[Command] get_table_stats called with connection_id: {}, database: '{}', schema: '{}', table: '{}'",
+        connection_id, database, schema, table);
+
+    let connector = state.get_connection(&connection_id).await.map_err(|e| {
+        error!("[Command] get_table_stats - failed to get connection: {:?}", e);
+        e
+    })?;
+
+    let result = connector.get_table_stats(&database, &schema, &table).await;
+
+    if let Err(e) = &result {
+        error!("[Command] get_table_stats failed for {}.{}.{}: {:?}", database, schema, table, e);
+    }
+
+    result
+}
@@ -5,9 +5,13 @@ use crate::db::connector::DatabaseDriver;
 use crate::db::ConnectionConfig;
 use crate::error::AppError;
 use crate::state::AppState;
+use crate::storage::bundle::{self, BundleImportSummary, ConflictResolution};
+use crate::storage::import::{self, ImportCandidate, ImportFormat};
+use crate::storage::vault::MasterPasswordStatus;
 use crate::storage::{
-    AddQueryHistoryInput, CreateSavedQueryInput, QueryHistoryEntry, SavedQuery,
-    UpdateSavedQueryInput, Workspace, WorkspaceConfig,
+    AddQueryHistoryInput, CreateSavedQueryInput, HistoryRetention, QueryHistoryEntry,
+    QueryHistoryPage, QueryHistorySearch, SavedQuery, UpdateSavedQueryInput, Workspace,
+    WorkspaceConfig,
 };
 
 /// A connection without the password for frontend display
@@ -20,6 +24,7 @@ pub struct ConnectionInfo {
     pub username: String,
     pub database: Option<String>,
     pub driver: DatabaseDriver,
+    pub file_path: Option<String>,
 }
 
 // ==================== Connection Commands ====================
@@ -44,6 +49,7 @@ pub async fn save_connection(
         username: saved.username,
         database: saved.database,
         driver: saved.driver,
+        file_path: saved.file_path,
     })
 }
 
@@ -82,6 +88,7 @@ pub async fn list_connections(
             username: c.username,
             database: c.database,
             driver: c.driver,
+            file_path: c.file_path,
         })
         .collect())
 }
@@ -118,9 +125,230 @@ pub async fn get_connection_config(
         .await?
         .ok_or_else(|| AppError::ConnectionNotFound(id))?;
 
-    let password = storage.connections.decrypt_password(&saved.encrypted_password)?;
+    let (password, ssh_secret) = storage.connections.load_secrets(&saved).await?;
 
-    Ok(saved.to_config(password))
+    Ok(saved.to_config(password, ssh_secret))
+}
+
+/// Preview importing connections from a URL list, .pgpass, pg_service.conf, my.cnf or .env file 👀
+///
+/// Reads `text` if given, else the file at `path` (or the format's usual
+/// location, like `~/.pgpass`). Nothing is saved yet - the result lists what
+/// `import_connections` would create and which saved connections each
+/// duplicates (same host, port, user and database).
+#[tauri::command]
+pub async fn preview_connection_import(
+    state: State<'_, AppState>,
+    format: ImportFormat,
+    path: Option<String>,
+    text: Option<String>,
+) -> Result<Vec<ImportCandidate>, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    let source = import::read_source(format, path.as_deref(), text)?;
+    let parsed = import::parse(format, &source)?;
+    let existing = storage.connections.list().await?;
+
+    Ok(import::preview(&parsed, &existing))
+}
+
+/// Import connections, saving them like any other connection 📥
+///
+/// `selected` holds indexes into the preview list; without it, every entry
+/// that doesn't duplicate a saved connection is imported. An index past the
+/// end of the list fails the whole import before anything is saved.
+#[tauri::command]
+pub async fn import_connections(
+    state: State<'_, AppState>,
+    format: ImportFormat,
+    path: Option<String>,
+    text: Option<String>,
+    selected: Option<Vec<usize>>,
+) -> Result<Vec<ConnectionInfo>, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    let source = import::read_source(format, path.as_deref(), text)?;
+    let parsed = import::parse(format, &source)?;
+    let existing = storage.connections.list().await?;
+    let candidates = import::preview(&parsed, &existing);
+
+    if let Some(index) = selected.iter().flatten().find(|&&index| index >= parsed.len()) {
+        return Err(AppError::Validation(format!(
+            "Import entry {} doesn't exist (the source has {} connections)",
+            index,
+            parsed.len()
+        )));
+    }
+
+    let mut imported = Vec::new();
+    for (index, connection) in parsed.iter().enumerate() {
+        let wanted = match &selected {
+            Some(selected) => selected.contains(&index),
+            None => candidates[index].duplicate_of.is_none(),
+        };
+        if !wanted {
+            continue;
+        }
+
+        let saved = storage.connections.save(&connection.config).await?;
+        imported.push(ConnectionInfo {
+            id: saved.id,
+            name: saved.name,
+            host: saved.host,
+            port: saved.port,
+            username: saved.username,
+            database: saved.database,
+            driver: saved.driver,
+            file_path: saved.file_path,
+        });
+    }
+
+    Ok(imported)
+}
+
+/// Export all connections, workspaces and saved queries to a passphrase-protected file 📦
+///
+/// Passwords are decrypted and encrypted again under the passphrase, so the
+/// file can be imported on another machine.
+#[tauri::command]
+pub async fn export_bundle(
+    state: State<'_, AppState>,
+    path: String,
+    passphrase: String,
+) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    let contents = bundle::export(
+        &storage.connections,
+        &storage.workspaces,
+        &storage.saved_queries,
+        &passphrase,
+    )
+    .await?;
+    std::fs::write(&path, contents)?;
+
+    Ok(())
+}
+
+/// Import a file made by `export_bundle`, merging it into the saved data 📥
+///
+/// `on_conflict` decides what happens to connections, workspaces and saved
+/// queries that already exist here. Items that fail to save are listed in the
+/// summary's `failed`; the rest are still imported.
+#[tauri::command]
+pub async fn import_bundle(
+    state: State<'_, AppState>,
+    path: String,
+    passphrase: String,
+    on_conflict: ConflictResolution,
+) -> Result<BundleImportSummary, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    let contents = std::fs::read_to_string(&path)?;
+    bundle::import(
+        &storage.connections,
+        &storage.workspaces,
+        &storage.saved_queries,
+        &contents,
+        &passphrase,
+        on_conflict,
+    )
+    .await
+}
+
+// ==================== Master Password Commands ====================
+
+#[tauri::command]
+pub async fn get_master_password_status(
+    state: State<'_, AppState>,
+) -> Result<MasterPasswordStatus, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    Ok(storage.connections.vault().status())
+}
+
+/// Protect saved credentials with a master password 🔑
+///
+/// Every stored password is re-encrypted under the new key. With
+/// `auto_lock_minutes`, credentials lock again after that long unused.
+#[tauri::command]
+pub async fn enable_master_password(
+    state: State<'_, AppState>,
+    passphrase: String,
+    auto_lock_minutes: Option<u32>,
+) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.connections.vault().enable(&passphrase, auto_lock_minutes).await
+}
+
+#[tauri::command]
+pub async fn disable_master_password(
+    state: State<'_, AppState>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.connections.vault().disable(&passphrase).await
+}
+
+#[tauri::command]
+pub async fn set_master_password_auto_lock(
+    state: State<'_, AppState>,
+    auto_lock_minutes: Option<u32>,
+) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.connections.vault().set_auto_lock(auto_lock_minutes).await
+}
+
+/// Unlock saved credentials - call at startup when the status says locked 🔓
+#[tauri::command]
+pub async fn unlock_credentials(
+    state: State<'_, AppState>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.connections.vault().unlock(&passphrase).await
+}
+
+#[tauri::command]
+pub async fn lock_credentials(state: State<'_, AppState>) -> Result<(), AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.connections.vault().lock();
+    Ok(())
 }
 
 // ==================== Workspace Commands ====================
@@ -164,6 +392,20 @@ pub async fn update_workspace(
     storage.workspaces.update(&id, &config).await
 }
 
+#[tauri::command]
+pub async fn set_workspace_history_retention(
+    state: State<'_, AppState>,
+    id: String,
+    retention: HistoryRetention,
+) -> Result<Workspace, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.workspaces.set_history_retention(&id, &retention).await
+}
+
 #[tauri::command]
 pub async fn delete_workspace(
     state: State<'_, AppState>,
@@ -222,6 +464,10 @@ pub async fn move_connection_between_workspaces(
 
 // ==================== Query History Commands ====================
 
+/// Add a history entry by hand.
+///
+/// Queries on connections opened with a `saved_connection_id` are recorded
+/// by the backend already - don't add those again.
 #[tauri::command]
 pub async fn add_query_history(
     state: State<'_, AppState>,
@@ -252,6 +498,33 @@ pub async fn list_query_history(
         .await
 }
 
+#[tauri::command]
+pub async fn search_query_history(
+    state: State<'_, AppState>,
+    search: QueryHistorySearch,
+) -> Result<QueryHistoryPage, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.query_history.search(&search).await
+}
+
+#[tauri::command]
+pub async fn pin_query_history(
+    state: State<'_, AppState>,
+    id: String,
+    pinned: bool,
+) -> Result<QueryHistoryEntry, AppError> {
+    let storage = state
+        .storage
+        .get()
+        .ok_or_else(|| AppError::Storage("Storage not initialized".to_string()))?;
+
+    storage.query_history.set_pinned(&id, pinned).await
+}
+
 #[tauri::command]
 pub async fn delete_query_history(
     state: State<'_, AppState>,
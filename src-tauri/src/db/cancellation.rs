@@ -0,0 +1,162 @@
+//! Tracking of in-flight queries so they can be cancelled server-side! 🛑✨
+//!
+//! Every query that runs on behalf of an editor session registers the backend
+//! id of the connection it runs on (PostgreSQL `pg_backend_pid()`, MySQL
+//! `CONNECTION_ID()`). A cancel request looks that id up and asks the server
+//! to stop the statement from a *different* connection~
+//!
+//! Registrations are RAII guards, so an entry disappears as soon as the query
+//! finishes, fails, or its future is dropped. 💪
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::AppError;
+
+/// A query that is currently running for a session
+struct RunningQuery {
+    /// Unique token so a finished query never unregisters a newer one
+    token: u64,
+    /// Server-side id of the connection executing the query
+    backend_id: i64,
+    /// Set once a cancel request was sent for this query
+    cancelled: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    queries: Mutex<HashMap<String, RunningQuery>>,
+    next_token: AtomicU64,
+}
+
+/// Registry of running queries keyed by session id (usually the editor tab id).
+#[derive(Clone, Default)]
+pub struct RunningQueries {
+    inner: Arc<Inner>,
+}
+
+impl RunningQueries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a query running on `backend_id` for `session_id`.
+    ///
+    /// The entry lives until the returned guard is dropped.
+    pub fn register(&self, session_id: &str, backend_id: i64) -> RunningQueryGuard {
+        let token = self.inner.next_token.fetch_add(1, Ordering::Relaxed);
+        let mut queries = self.inner.queries.lock().unwrap_or_else(|e| e.into_inner());
+        queries.insert(
+            session_id.to_string(),
+            RunningQuery {
+                token,
+                backend_id,
+                cancelled: false,
+            },
+        );
+
+        RunningQueryGuard {
+            inner: self.inner.clone(),
+            session_id: session_id.to_string(),
+            token,
+        }
+    }
+
+    /// Mark the session's running query as cancelled and return its backend id.
+    ///
+    /// Returns `None` when nothing is running for the session.
+    pub fn mark_cancelled(&self, session_id: &str) -> Option<i64> {
+        let mut queries = self.inner.queries.lock().unwrap_or_else(|e| e.into_inner());
+        queries.get_mut(session_id).map(|query| {
+            query.cancelled = true;
+            query.backend_id
+        })
+    }
+}
+
+/// Keeps a running query registered until dropped.
+pub struct RunningQueryGuard {
+    inner: Arc<Inner>,
+    session_id: String,
+    token: u64,
+}
+
+impl RunningQueryGuard {
+    /// Whether a cancel request was sent while this query was running
+    pub fn was_cancelled(&self) -> bool {
+        let queries = self.inner.queries.lock().unwrap_or_else(|e| e.into_inner());
+        queries
+            .get(&self.session_id)
+            .is_some_and(|query| query.token == self.token && query.cancelled)
+    }
+
+    /// Replace the error of a cancelled query with `AppError::QueryCancelled`.
+    ///
+    /// The server reports cancellation as an ordinary database error, so the
+    /// cancel flag is what tells a user-initiated stop apart from a failure.
+    pub fn map_error(&self, error: AppError) -> AppError {
+        if self.was_cancelled() {
+            AppError::QueryCancelled
+        } else {
+            error
+        }
+    }
+}
+
+impl Drop for RunningQueryGuard {
+    fn drop(&mut self) {
+        let mut queries = self.inner.queries.lock().unwrap_or_else(|e| e.into_inner());
+        if queries.get(&self.session_id).is_some_and(|query| query.token == self.token) {
+            queries.remove(&self.session_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_cancel() {
+        let running = RunningQueries::new();
+        let guard = running.register("tab-1", 42);
+
+        assert!(!guard.was_cancelled());
+        assert_eq!(running.mark_cancelled("tab-1"), Some(42));
+        assert!(guard.was_cancelled());
+        assert!(matches!(
+            guard.map_error(AppError::Validation("boom".into())),
+            AppError::QueryCancelled
+        ));
+    }
+
+    #[test]
+    fn test_guard_drop_unregisters() {
+        let running = RunningQueries::new();
+        drop(running.register("tab-1", 42));
+
+        assert_eq!(running.mark_cancelled("tab-1"), None);
+    }
+
+    #[test]
+    fn test_errors_pass_through_without_cancel() {
+        let running = RunningQueries::new();
+        let guard = running.register("tab-1", 42);
+
+        assert!(matches!(
+            guard.map_error(AppError::Validation("boom".into())),
+            AppError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn test_stale_guard_keeps_newer_registration() {
+        let running = RunningQueries::new();
+        let old = running.register("tab-1", 1);
+        let _new = running.register("tab-1", 2);
+        drop(old);
+
+        assert_eq!(running.mark_cancelled("tab-1"), Some(2));
+    }
+}
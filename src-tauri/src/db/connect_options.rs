@@ -0,0 +1,233 @@
+//! Typed connect options for MySQL and PostgreSQL! 🧩✨
+//!
+//! Connection URLs look simple until the password contains `@`, `/`, `:` or
+//! `#` - then `user:p@ss@host` means something else entirely, and the URL
+//! (password included) ends up in error messages. So we never build URLs:
+//! every setting goes through sqlx's `PgConnectOptions`/`MySqlConnectOptions`
+//! builders, which take each value as-is.
+//!
+//! The host field understands a few shapes:
+//! - `db.example.com`, `10.0.0.5` - a TCP host
+//! - `::1` or `[::1]` - an IPv6 address (brackets optional)
+//! - `/var/run/postgresql` - a Unix socket (PostgreSQL: the socket directory,
+//!   MySQL: the socket file)
+//!
+//! `ConnectionConfig::params` adds driver parameters on top - see
+//! `pg_options` and `mysql_options` for which ones each driver knows. 🔧
+
+use sqlx::mysql::MySqlConnectOptions;
+use sqlx::postgres::PgConnectOptions;
+
+use super::connector::ConnectionConfig;
+use crate::error::AppError;
+
+/// Where the host field points
+#[derive(Debug, PartialEq, Eq)]
+enum HostSpec<'a> {
+    Tcp(&'a str),
+    Socket(&'a str),
+}
+
+/// Work out whether the host is a socket path, and strip IPv6 brackets
+fn host_spec(host: &str) -> HostSpec<'_> {
+    let host = host.trim();
+    if host.starts_with('/') {
+        return HostSpec::Socket(host);
+    }
+    if host.is_empty() {
+        return HostSpec::Tcp("localhost");
+    }
+    HostSpec::Tcp(
+        host.strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host),
+    )
+}
+
+fn parse_param<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, AppError> {
+    value
+        .parse()
+        .map_err(|_| AppError::Validation(format!("Invalid value '{}' for connection parameter '{}'", value, key)))
+}
+
+/// Escape a value for PostgreSQL's `options` startup parameter (`-c key=value ...`)
+fn escape_pg_option(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(' ', "\\ ")
+}
+
+/// Parameters `pg_options` sets on the builder instead of sending them as server settings
+pub const PG_PARAMS: &[&str] = &["application_name", "statement_cache_capacity", "extra_float_digits"];
+
+/// Parameters `mysql_options` accepts
+pub const MYSQL_PARAMS: &[&str] = &[
+    "charset",
+    "collation",
+    "timezone",
+    "statement_cache_capacity",
+    "pipes_as_concat",
+    "no_engine_substitution",
+    "set_names",
+];
+
+/// PostgreSQL connect options for one database of the connection 🐘
+///
+/// Known parameters:
+/// - `application_name`, `statement_cache_capacity`, `extra_float_digits`
+/// - anything else is sent as a server setting (`-c key=value`), e.g.
+///   `search_path`, `TimeZone` or `lock_timeout`
+///
+/// TLS settings are applied too. `.pgpass` and `PG*` environment variables
+/// are not read - the saved connection is the single source of truth~
+///
+/// # Errors
+/// Returns `AppError::Validation` if a known parameter has an invalid value.
+pub fn pg_options(config: &ConnectionConfig, database: &str) -> Result<PgConnectOptions, AppError> {
+    let mut options = PgConnectOptions::new_without_pgpass()
+        .port(config.port)
+        .username(&config.username)
+        .password(&config.password)
+        .database(database);
+    options = match host_spec(&config.host) {
+        HostSpec::Tcp(host) => options.host(host),
+        HostSpec::Socket(path) => options.socket(path),
+    };
+
+    let mut settings = Vec::new();
+    for (key, value) in &config.params {
+        options = match key.as_str() {
+            "application_name" => options.application_name(value),
+            "statement_cache_capacity" => options.statement_cache_capacity(parse_param(key, value)?),
+            "extra_float_digits" => options.extra_float_digits(parse_param::<i8>(key, value)?),
+            _ => {
+                settings.push((key.as_str(), escape_pg_option(value)));
+                options
+            }
+        };
+    }
+    if !settings.is_empty() {
+        options = options.options(settings);
+    }
+
+    Ok(config.tls.apply_pg(options))
+}
+
+/// MySQL connect options for the connection 🐬
+///
+/// Known parameters: `charset`, `collation`, `timezone`,
+/// `statement_cache_capacity`, `pipes_as_concat`, `no_engine_substitution`
+/// and `set_names`. MySQL has no generic way to pass session settings at
+/// connect time, so anything else is rejected rather than silently ignored.
+///
+/// # Errors
+/// Returns `AppError::Validation` for unknown parameters or invalid values.
+pub fn mysql_options(config: &ConnectionConfig) -> Result<MySqlConnectOptions, AppError> {
+    let mut options = MySqlConnectOptions::new()
+        .port(config.port)
+        .username(&config.username)
+        .password(&config.password);
+    options = match host_spec(&config.host) {
+        HostSpec::Tcp(host) => options.host(host),
+        HostSpec::Socket(path) => options.socket(path),
+    };
+    if let Some(database) = &config.database {
+        options = options.database(database);
+    }
+
+    for (key, value) in &config.params {
+        options = match key.as_str() {
+            "charset" => options.charset(value),
+            "collation" => options.collation(value),
+            "timezone" => options.timezone(Some(value.clone())),
+            "statement_cache_capacity" => options.statement_cache_capacity(parse_param(key, value)?),
+            "pipes_as_concat" => options.pipes_as_concat(parse_param(key, value)?),
+            "no_engine_substitution" => options.no_engine_substitution(parse_param(key, value)?),
+            "set_names" => options.set_names(parse_param(key, value)?),
+            _ => {
+                return Err(AppError::Validation(format!(
+                    "Unknown MySQL connection parameter '{}'",
+                    key
+                )))
+            }
+        };
+    }
+
+    Ok(config.tls.apply_mysql(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tls::TlsConfig;
+    use crate::db::{DatabaseDriver, QueryLimits};
+    use std::collections::BTreeMap;
+
+    fn config(host: &str, password: &str) -> ConnectionConfig {
+        ConnectionConfig {
+            name: "test".to_string(),
+            host: host.to_string(),
+            port: 5432,
+            username: "app".to_string(),
+            password: password.to_string(),
+            database: Some("shop".to_string()),
+            driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        }
+    }
+
+    #[test]
+    fn test_host_spec() {
+        assert_eq!(host_spec("db.example.com"), HostSpec::Tcp("db.example.com"));
+        assert_eq!(host_spec("[::1]"), HostSpec::Tcp("::1"));
+        assert_eq!(host_spec("fe80::1"), HostSpec::Tcp("fe80::1"));
+        assert_eq!(host_spec(""), HostSpec::Tcp("localhost"));
+        assert_eq!(host_spec("/var/run/postgresql"), HostSpec::Socket("/var/run/postgresql"));
+    }
+
+    #[test]
+    fn test_pg_options_take_values_as_is() {
+        let options = pg_options(&config("[::1]", "p@ss:w/rd#1"), "shop").unwrap();
+        assert_eq!(options.get_host(), "::1");
+        assert_eq!(options.get_port(), 5432);
+        assert_eq!(options.get_username(), "app");
+        assert_eq!(options.get_database(), Some("shop"));
+
+        let options = pg_options(&config("/var/run/postgresql", ""), "shop").unwrap();
+        assert_eq!(
+            options.get_socket().map(|p| p.to_string_lossy().into_owned()),
+            Some("/var/run/postgresql".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pg_params() {
+        let mut config = config("localhost", "secret");
+        config.params.insert("application_name".to_string(), "anko".to_string());
+        config.params.insert("search_path".to_string(), "app public".to_string());
+
+        let options = pg_options(&config, "shop").unwrap();
+        assert_eq!(options.get_application_name(), Some("anko"));
+        assert_eq!(options.get_options(), Some("-c search_path=app\\ public"));
+
+        config.params.insert("statement_cache_capacity".to_string(), "lots".to_string());
+        assert!(matches!(pg_options(&config, "shop"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_mysql_params() {
+        let mut config = config("db.example.com", "secret");
+        config.params.insert("charset".to_string(), "latin1".to_string());
+
+        let options = mysql_options(&config).unwrap();
+        assert_eq!(options.get_charset(), "latin1");
+        assert_eq!(options.get_database(), Some("shop"));
+
+        config.params.insert("search_path".to_string(), "public".to_string());
+        assert!(matches!(mysql_options(&config), Err(AppError::Validation(_))));
+    }
+}
@@ -8,15 +8,25 @@
 //! can work with a consistent API. Think of it as the universal translator for databases! 🎯
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+use super::params::QueryParam;
+use super::stream::{ResultPage, RowSink, StreamOptions, StreamSummary};
+use super::tls::TlsConfig;
+use super::tunnel::SshTunnelConfig;
 use crate::error::AppError;
+use crate::storage::credentials::CredentialBackend;
 
 /// Configuration for establishing a database connection! 🚀
 ///
 /// This struct holds all the information needed to connect to your database.
 /// Passwords are stored in plain text here but will be encrypted before
 /// saving to local storage - security is our top priority! 🔐✨
+///
+/// SQLite connections only need `file_path` - host, port and credentials
+/// are ignored for file-based databases~
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionConfig {
     pub name: String,
@@ -26,25 +36,90 @@ pub struct ConnectionConfig {
     pub password: String,
     pub database: Option<String>,
     pub driver: DatabaseDriver,
+    /// Path to the database file (SQLite only)
+    #[serde(default)]
+    pub file_path: Option<String>,
+    /// Default timeout and row limit for this connection's queries
+    #[serde(default)]
+    pub limits: QueryLimits,
+    /// Reach the database through this SSH server (MySQL and PostgreSQL only)
+    #[serde(default)]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    /// TLS mode and certificates (MySQL and PostgreSQL only)
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Extra driver parameters, like `application_name` or `charset`
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// Where the password and SSH secret are kept once saved
+    #[serde(default)]
+    pub credential_backend: CredentialBackend,
+    /// Record this connection's queries in history (turn off for sensitive environments)
+    #[serde(default = "default_record_history")]
+    pub record_history: bool,
+}
+
+fn default_record_history() -> bool {
+    true
+}
+
+impl ConnectionConfig {
+    /// Every secret in the config, for scrubbing them out of error messages 🙈
+    pub fn secrets(&self) -> Vec<&str> {
+        let mut secrets = vec![self.password.as_str()];
+        secrets.extend(self.ssh_tunnel.as_ref().and_then(|tunnel| tunnel.secret()));
+        secrets
+    }
+}
+
+/// Timeout and row limit for queries! ⏱️
+///
+/// Stored per connection as defaults, and passed per call to override them.
+/// `None` means "no limit" in a connection's defaults, and "use the
+/// connection's default" in a per-call override~
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryLimits {
+    /// Stop the statement after this many milliseconds
+    ///
+    /// **MySQL:** `max_execution_time`, which only applies to `SELECT`
+    /// **PostgreSQL:** `statement_timeout`
+    /// **SQLite:** A progress handler interrupts the statement
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Return at most this many rows (`QueryResult::truncated` says if more were left)
+    #[serde(default)]
+    pub max_rows: Option<u64>,
+}
+
+impl QueryLimits {
+    /// Fill in whatever this override leaves open from `defaults`
+    pub fn or(self, defaults: QueryLimits) -> QueryLimits {
+        QueryLimits {
+            timeout_ms: self.timeout_ms.or(defaults.timeout_ms),
+            max_rows: self.max_rows.or(defaults.max_rows),
+        }
+    }
+
+    /// Row cap usable as a loop bound
+    pub fn row_cap(&self) -> usize {
+        self.max_rows.map_or(usize::MAX, |n| usize::try_from(n).unwrap_or(usize::MAX))
+    }
 }
 
 /// Which database system you want to connect to! 🎯⚡
 ///
-/// Currently supports MySQL and PostgreSQL with more amazing databases
+/// Currently supports MySQL, PostgreSQL and SQLite with more amazing databases
 /// coming in the future! The architecture makes adding new drivers a breeze~ 🌟
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum DatabaseDriver {
     /// MySQL database (the classic!)
+    #[default]
     MySQL,
     /// PostgreSQL database (the powerful one!)
     PostgreSQL,
-}
-
-impl Default for DatabaseDriver {
-    fn default() -> Self {
-        Self::MySQL
-    }
+    /// SQLite database file (the tiny one!)
+    SQLite,
 }
 
 /// The awesome result of executing a SQL query! 🎉✨
@@ -68,6 +143,31 @@ pub struct QueryResult {
     /// Debug info: the actual query executed (after adding context like USE db)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub executed_query: Option<String>,
+    /// Transaction state of the editor session after the query ran
+    #[serde(default)]
+    pub transaction_state: TransactionState,
+    /// Why the statement failed (only set for statements of a script)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// True if the row limit cut the result short
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Where an editor session stands transaction-wise! 🔄
+///
+/// Only sessions (see `execute_with_context`) can hold a transaction open -
+/// queries without a session always report `Idle`~
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionState {
+    /// Autocommit: every statement commits on its own
+    #[default]
+    Idle,
+    /// A transaction is open and waiting for COMMIT or ROLLBACK
+    InTransaction,
+    /// A statement failed inside the transaction (PostgreSQL) - only ROLLBACK works now
+    Failed,
 }
 
 /// Metadata about a column in a query result! 🌸
@@ -87,6 +187,7 @@ pub struct ColumnInfo {
 ///
 /// In MySQL: represents a database
 /// In PostgreSQL: can represent either a database OR a schema
+/// In SQLite: represents an attached database (`main`, or any `ATTACH`ed file)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaInfo {
     /// Database or schema name
@@ -128,6 +229,163 @@ pub struct ColumnDetail {
     pub extra: Option<String>,
 }
 
+/// An index on a table! 🗂️
+///
+/// Everything `ColumnDetail.key` can't tell you: which columns, in which
+/// order, how the index is built and what it costs on disk~
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    /// Index name
+    pub name: String,
+    /// Indexed columns in index order (expressions as their SQL text)
+    pub columns: Vec<String>,
+    /// Does the index enforce uniqueness?
+    pub unique: bool,
+    /// Is this the primary key's index?
+    pub primary: bool,
+    /// Access method: "btree", "hash", "gin", "gist", "fulltext", "spatial", ...
+    pub method: String,
+    /// WHERE clause of a partial index
+    pub predicate: Option<String>,
+    /// Size on disk in bytes (None if the database doesn't say)
+    pub size_bytes: Option<i64>,
+}
+
+/// What kind of rule a constraint is! 📏
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    PrimaryKey,
+    ForeignKey,
+    Unique,
+    Check,
+    /// PostgreSQL `EXCLUDE` constraints
+    Exclusion,
+}
+
+/// What a foreign key does when the row it points to changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferentialAction {
+    #[default]
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+    SetDefault,
+}
+
+impl ReferentialAction {
+    /// Parse the SQL spelling ("CASCADE", "SET NULL", ...), defaulting to `NoAction`
+    pub fn from_sql(rule: &str) -> Self {
+        match rule.trim().to_ascii_uppercase().as_str() {
+            "RESTRICT" => ReferentialAction::Restrict,
+            "CASCADE" => ReferentialAction::Cascade,
+            "SET NULL" => ReferentialAction::SetNull,
+            "SET DEFAULT" => ReferentialAction::SetDefault,
+            _ => ReferentialAction::NoAction,
+        }
+    }
+}
+
+/// The other end of a foreign key! 🔗
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyReference {
+    /// Schema (database on MySQL) of the referenced table
+    pub schema: String,
+    /// Referenced table
+    pub table: String,
+    /// Referenced columns, matching the constraint's columns in order
+    pub columns: Vec<String>,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+}
+
+/// A constraint on a table! 📏✨
+///
+/// Lets the schema browser follow foreign keys to related rows and
+/// show what a table actually enforces~
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintInfo {
+    /// Constraint name
+    pub name: String,
+    pub kind: ConstraintKind,
+    /// Constrained columns in order (empty for CHECK and EXCLUDE)
+    pub columns: Vec<String>,
+    /// Where a foreign key points (foreign keys only)
+    pub references: Option<ForeignKeyReference>,
+    /// The CHECK expression, or the full EXCLUDE definition
+    pub expression: Option<String>,
+}
+
+/// Kinds of schema objects beyond tables and views! 🧩
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaObjectKind {
+    Function,
+    Procedure,
+    Trigger,
+    Sequence,
+    /// PostgreSQL only - information_schema leaves these out
+    MaterializedView,
+    /// User-defined types: enums, composites, domains and ranges (PostgreSQL only)
+    Type,
+}
+
+/// A function, trigger, sequence or other schema object! 🧩✨
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaObject {
+    /// Object name
+    pub name: String,
+    /// Schema (database on MySQL) the object lives in
+    pub schema: String,
+    pub kind: SchemaObjectKind,
+    /// Table a trigger fires on (triggers only)
+    pub table: Option<String>,
+    /// Routine argument list, e.g. `a integer, b text` (tells PostgreSQL overloads apart)
+    pub arguments: Option<String>,
+    /// Function return type, sequence data type, or the flavor of a custom type
+    pub result_type: Option<String>,
+    /// Source text of routines, triggers and materialized views, when the server shares it
+    pub definition: Option<String>,
+}
+
+/// Size and maintenance statistics for a table! 📊
+///
+/// Sizes are in bytes and add up: `total_bytes` = `table_bytes` +
+/// `index_bytes` + `toast_bytes`. Fields a database doesn't track stay `None`~
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableStats {
+    /// Estimated row count from the planner statistics (exact on SQLite)
+    pub estimated_rows: Option<i64>,
+    /// Everything the table occupies on disk
+    pub total_bytes: Option<i64>,
+    /// The table's own data
+    pub table_bytes: Option<i64>,
+    /// All indexes on the table
+    pub index_bytes: Option<i64>,
+    /// Out-of-line storage for large values (PostgreSQL only)
+    pub toast_bytes: Option<i64>,
+    /// Rows waiting to be vacuumed (PostgreSQL only)
+    pub dead_tuples: Option<i64>,
+    /// Last manual VACUUM (PostgreSQL only)
+    pub last_vacuum: Option<DateTime<Utc>>,
+    /// Last autovacuum run (PostgreSQL only)
+    pub last_autovacuum: Option<DateTime<Utc>>,
+    /// Last manual ANALYZE (PostgreSQL only)
+    pub last_analyze: Option<DateTime<Utc>>,
+    /// Last autoanalyze run (PostgreSQL only)
+    pub last_autoanalyze: Option<DateTime<Utc>>,
+    /// Storage engine, e.g. InnoDB (MySQL only)
+    pub engine: Option<String>,
+    /// Allocated but unused bytes (MySQL only)
+    pub data_free: Option<i64>,
+    /// Next AUTO_INCREMENT value (MySQL only)
+    pub auto_increment: Option<i64>,
+    /// Default collation of the table (MySQL only)
+    pub collation: Option<String>,
+}
+
 /// The magical trait that unifies all database systems! ✨🚀
 ///
 /// This trait provides a consistent interface for working with different databases.
@@ -155,6 +413,12 @@ pub trait DatabaseConnector: Send + Sync {
     /// the error message will help you debug! You got this! 💪
     async fn execute(&self, query: &str) -> Result<QueryResult, AppError>;
 
+    /// Which database driver this connector speaks! 🏷️
+    fn driver(&self) -> DatabaseDriver;
+
+    /// The connection's default timeout and row limit ⏱️
+    fn limits(&self) -> QueryLimits;
+
     /// Execute a query with database/schema context switching! 🎯✨
     ///
     /// This is super powerful because it handles the different ways MySQL and
@@ -170,20 +434,205 @@ pub trait DatabaseConnector: Send + Sync {
     /// - Uses `context` parameter to set `search_path` to schema
     /// - Creates pools on-demand for each database you access
     ///
+    /// **SQLite behavior:**
+    /// - Both parameters are ignored - reference attached databases with
+    ///   qualified names like `other.table` instead
+    ///
+    /// When a `session_id` is given, the query runs on a connection pinned to
+    /// that session, so `USE`, `search_path`, session variables and temp tables
+    /// carry over between the session's queries. It is also registered as
+    /// running for that session so `cancel_query` can stop it~
+    ///
     /// # Arguments
     /// * `query` - Your SQL query to execute
     /// * `database` - Database name (PostgreSQL only, MySQL ignores this)
     /// * `context` - Schema/database context (MySQL: database, PostgreSQL: schema)
+    /// * `session_id` - Editor session (tab) running the query, for cancellation
     ///
     /// # Returns
     /// A `QueryResult` with debug info showing what was actually executed! 🌟
+    ///
+    /// # Errors
+    /// Returns `AppError::QueryCancelled` if the query was stopped via `cancel_query`.
     async fn execute_with_context(
         &self,
         query: &str,
         database: Option<&str>,
         context: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<QueryResult, AppError> {
+        self.execute_with_params(query, &[], database, context, session_id, &QueryLimits::default())
+            .await
+    }
+
+    /// Execute a query with bound parameters! 🧷
+    ///
+    /// Works exactly like `execute_with_context`, with `params` bound in order
+    /// to the driver's placeholders (`?` for MySQL/SQLite, `$1, $2, ...` for
+    /// PostgreSQL). Use `params::resolve` first for `:name` placeholders.
+    ///
+    /// `limits` overrides the connection's default timeout and row limit for
+    /// this one call. Rows past the limit are dropped and the result says
+    /// `truncated`~
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` for a parameter that can't be bound,
+    /// `AppError::QueryTimeout` if the timeout hit, and
+    /// `AppError::QueryCancelled` if the query was stopped via `cancel_query`.
+    async fn execute_with_params(
+        &self,
+        query: &str,
+        params: &[QueryParam],
+        database: Option<&str>,
+        context: Option<&str>,
+        session_id: Option<&str>,
+        limits: &QueryLimits,
     ) -> Result<QueryResult, AppError>;
 
+    /// Stream a query's rows to the frontend in batches! 🌊
+    ///
+    /// Context and sessions work exactly like `execute_with_context`, but rows
+    /// are converted as they arrive and handed to `sink` in batches instead of
+    /// being collected, so huge results never sit in memory all at once.
+    /// Reading stops after `options.max_rows` rows, and the timeout applies
+    /// just like in `execute_with_params`~
+    ///
+    /// # Arguments
+    /// * `query` - Your SQL query to execute
+    /// * `database` - Database name (PostgreSQL only, MySQL ignores this)
+    /// * `context` - Schema/database context (MySQL: database, PostgreSQL: schema)
+    /// * `session_id` - Editor session (tab) running the query
+    /// * `limits` - Timeout for this call (`None` falls back to the connection's);
+    ///   the row cap comes from `options`
+    /// * `options` - Batch size and row cap
+    /// * `sink` - Receives the column info and then the row batches
+    ///
+    /// # Returns
+    /// A `StreamSummary` with the row count, timing and whether the cap hit! 🎯
+    ///
+    /// # Errors
+    /// Returns `AppError::QueryTimeout` if the timeout hit,
+    /// `AppError::QueryCancelled` if the query was stopped via `cancel_query`,
+    /// or the sink's error if the frontend stopped listening.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_streaming(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        context: Option<&str>,
+        session_id: Option<&str>,
+        limits: &QueryLimits,
+        options: &StreamOptions,
+        sink: &RowSink<'_>,
+    ) -> Result<StreamSummary, AppError>;
+
+    /// Open a cursor for a query and read its first page! 📖
+    ///
+    /// **MySQL:** The result is read lazily off the session's connection, so
+    /// the server only sends what the pages ask for
+    /// **PostgreSQL:** A real `DECLARE ... CURSOR` on the session's backend
+    /// (`WITH HOLD` outside a transaction)
+    /// **SQLite:** Not supported - use `execute_streaming` instead
+    ///
+    /// Each session has at most one cursor. Opening another one, or running
+    /// any other query in the session, closes it.
+    ///
+    /// Cursors don't get the connection's timeout: the statement stays open
+    /// while someone pages through it, and MySQL's `max_execution_time` would
+    /// count that time too. Cancel with `cancel_query` instead.
+    ///
+    /// # Arguments
+    /// * `query` - A row-returning query
+    /// * `database` - Database name (PostgreSQL only, MySQL ignores this)
+    /// * `context` - Schema/database context (MySQL: database, PostgreSQL: schema)
+    /// * `session_id` - Editor session (tab) that owns the cursor
+    /// * `page_size` - Rows per page
+    async fn open_cursor(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        context: Option<&str>,
+        session_id: &str,
+        page_size: usize,
+    ) -> Result<ResultPage, AppError>;
+
+    /// Read the next page from the session's cursor! 📄
+    ///
+    /// The cursor closes by itself once a page comes back with `has_more: false`.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session has no open cursor.
+    async fn fetch_next_page(&self, session_id: &str, page_size: usize) -> Result<ResultPage, AppError>;
+
+    /// Close the session's cursor, if it has one
+    async fn close_cursor(&self, session_id: &str) -> Result<(), AppError>;
+
+    /// Stop the query currently running for a session! 🛑
+    ///
+    /// **MySQL:** Sends `KILL QUERY` for the session's connection id
+    /// **PostgreSQL:** Calls `pg_cancel_backend` with the session's backend PID
+    ///
+    /// The cancel is sent over a separate pooled connection, and the
+    /// interrupted `execute_with_context` call fails with `AppError::QueryCancelled`.
+    ///
+    /// # Arguments
+    /// * `session_id` - The session passed to `execute_with_context`
+    ///
+    /// # Returns
+    /// `true` if a running query was found and signalled, `false` if idle
+    async fn cancel_query(&self, session_id: &str) -> Result<bool, AppError>;
+
+    /// Release the connection pinned to a session (e.g. when its tab closes)! 🧹
+    ///
+    /// A query still running for the session is cancelled first. The
+    /// connection is closed instead of returned to the pool, so its session
+    /// state never leaks into other queries. Unknown sessions are ignored.
+    ///
+    /// **SQLite:** No-op - every session shares the single connection
+    ///
+    /// # Arguments
+    /// * `session_id` - The session passed to `execute_with_context`
+    async fn close_session(&self, session_id: &str) -> Result<(), AppError>;
+
+    /// Open a transaction in a session! 🔄
+    ///
+    /// Statements run in the session afterwards belong to the transaction
+    /// until `commit_transaction` or `rollback_transaction` - typing BEGIN,
+    /// COMMIT or ROLLBACK into the editor is tracked the same way.
+    ///
+    /// **SQLite:** Not supported - every session shares the single connection
+    ///
+    /// # Arguments
+    /// * `session_id` - The editor session to open the transaction in
+    /// * `database` - Database name (PostgreSQL only, like `execute_with_context`)
+    ///
+    /// # Returns
+    /// The session's new `TransactionState`
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session already has a transaction open.
+    async fn begin_transaction(&self, session_id: &str, database: Option<&str>) -> Result<TransactionState, AppError>;
+
+    /// Commit the session's transaction! ✅
+    ///
+    /// On PostgreSQL, committing a failed transaction rolls it back instead.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session has no transaction open.
+    async fn commit_transaction(&self, session_id: &str) -> Result<TransactionState, AppError>;
+
+    /// Roll back the session's transaction! ↩️
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session has no transaction open.
+    async fn rollback_transaction(&self, session_id: &str) -> Result<TransactionState, AppError>;
+
+    /// Current transaction state of a session (`Idle` for unknown sessions)
+    fn transaction_state(&self, session_id: &str) -> TransactionState;
+
+    /// Ids of the sessions that have a transaction open (or failed)
+    fn open_transactions(&self) -> Vec<String>;
+
     /// Get all databases accessible to this connection! 🌸
     ///
     /// Returns a list of databases you can work with. System databases
@@ -197,6 +646,7 @@ pub trait DatabaseConnector: Send + Sync {
     ///
     /// **MySQL:** Returns empty vec (MySQL doesn't have schemas separate from databases)
     /// **PostgreSQL:** Creates a pool for the database and lists its schemas
+    /// **SQLite:** Returns empty vec (attached databases play the role of schemas)
     ///
     /// # Arguments
     /// * `database` - The database to get schemas from
@@ -231,6 +681,78 @@ pub trait DatabaseConnector: Send + Sync {
     /// Vector of `ColumnDetail` with comprehensive metadata! ✨
     async fn get_columns(&self, database: &str, schema: &str, table: &str) -> Result<Vec<ColumnDetail>, AppError>;
 
+    /// Get the indexes of a table! 🗂️
+    ///
+    /// Primary key first, then the rest by name~
+    ///
+    /// # Arguments
+    /// * `database` - Database name
+    /// * `schema` - Schema name (ignored for MySQL)
+    /// * `table` - Table name
+    ///
+    /// # Returns
+    /// Vector of `IndexInfo` with columns, uniqueness, method and size! ✨
+    async fn get_indexes(&self, database: &str, schema: &str, table: &str) -> Result<Vec<IndexInfo>, AppError>;
+
+    /// Get the constraints of a table! 📏
+    ///
+    /// Primary key first, then unique constraints, foreign keys and the
+    /// rest - each group by name~
+    ///
+    /// **SQLite:** CHECK constraints aren't reported (they only live in the
+    /// table's CREATE statement)
+    ///
+    /// # Arguments
+    /// * `database` - Database name
+    /// * `schema` - Schema name (ignored for MySQL)
+    /// * `table` - Table name
+    ///
+    /// # Returns
+    /// Vector of `ConstraintInfo`, with foreign key targets and actions! 🔗
+    async fn get_constraints(&self, database: &str, schema: &str, table: &str)
+        -> Result<Vec<ConstraintInfo>, AppError>;
+
+    /// Get the CREATE statement for a table or view! 📜
+    ///
+    /// Ready to paste into a migration~ MySQL and SQLite hand back what the
+    /// server stores, PostgreSQL rebuilds it from the catalogs (columns,
+    /// defaults, identity, constraints, indexes and comments).
+    ///
+    /// # Arguments
+    /// * `database` - Database name
+    /// * `schema` - Schema name (ignored for MySQL)
+    /// * `name` - Table or view name
+    ///
+    /// # Returns
+    /// The SQL text, or `NotFound` if there's no such table or view
+    async fn get_object_ddl(&self, database: &str, schema: &str, name: &str) -> Result<String, AppError>;
+
+    /// List functions, procedures, triggers and friends in a schema! 🧩
+    ///
+    /// Kinds a database doesn't have (sequences on MySQL, anything but
+    /// triggers on SQLite) simply come back empty~
+    ///
+    /// # Arguments
+    /// * `database` - Database name
+    /// * `schema` - Schema name (ignored for MySQL and SQLite)
+    /// * `kind` - Which kind of object to list
+    ///
+    /// # Returns
+    /// Vector of `SchemaObject` sorted by name, with source text where available! 📜
+    async fn list_objects(&self, database: &str, schema: &str, kind: SchemaObjectKind)
+        -> Result<Vec<SchemaObject>, AppError>;
+
+    /// Get size and maintenance statistics for a table! 📊
+    ///
+    /// # Arguments
+    /// * `database` - Database name
+    /// * `schema` - Schema name (ignored for MySQL and SQLite)
+    /// * `table` - Table name
+    ///
+    /// # Returns
+    /// `TableStats`, or `NotFound` if there's no such table
+    async fn get_table_stats(&self, database: &str, schema: &str, table: &str) -> Result<TableStats, AppError>;
+
     /// Gracefully close the connection and clean up resources! 🌸
     ///
     /// Always call this when you're done with a connection to prevent
@@ -1,6 +1,15 @@
+pub mod cancellation;
+pub mod connect_options;
 pub mod connector;
 pub mod mysql;
+pub mod params;
 pub mod postgres;
 pub mod query_utils;
+pub mod script;
+pub mod session;
+pub mod sqlite;
+pub mod stream;
+pub mod tls;
+pub mod tunnel;
 
 pub use connector::*;
@@ -0,0 +1,356 @@
+//! Bind parameters for queries! 🧷✨
+//!
+//! Saved queries often carry placeholders like `:user_id` or `$1`. Instead of
+//! pasting values into the SQL text, the frontend sends a typed parameter
+//! list and we bind it through sqlx, so quoting is never our problem~
+//!
+//! - **Positional:** bound in order to the driver's own placeholders
+//!   (`?` for MySQL/SQLite, `$1, $2, ...` for PostgreSQL)
+//! - **Named:** `:name` placeholders are rewritten to the driver's syntax
+//!   first. Strings, comments, dollar quotes and `::` casts are left alone.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::database::Database;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySql, MySqlTypeInfo};
+use sqlx::postgres::{types::Oid, PgTypeInfo, Postgres};
+use sqlx::query::Query;
+use sqlx::sqlite::{Sqlite, SqliteTypeInfo};
+use sqlx::types::Json;
+use sqlx::{Encode, Type};
+
+use super::connector::DatabaseDriver;
+use super::script::{dollar_tag, is_identifier_byte, skip_block_comment, skip_line, skip_quoted};
+use crate::error::AppError;
+
+/// One typed parameter value 🎯
+///
+/// Serialized as `{ "type": "int", "value": 42 }` (`null` has no value).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum QueryParam {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    /// `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS[.fff]` or RFC 3339 with an offset
+    Date(String),
+    Json(serde_json::Value),
+    /// Raw bytes as an array of numbers
+    Bytes(Vec<u8>),
+}
+
+/// The parameters sent along with a query
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum QueryParams {
+    /// Bound in order to `?` / `$n` placeholders
+    Positional(Vec<QueryParam>),
+    /// Bound by name to `:name` placeholders
+    Named(HashMap<String, QueryParam>),
+}
+
+/// Turn a query and its parameters into SQL the driver understands! 🔄
+///
+/// Positional parameters pass through untouched. Named ones get their
+/// placeholders rewritten and come back in binding order.
+///
+/// # Errors
+/// Returns `AppError::Validation` if a named placeholder has no value.
+pub fn resolve(
+    query: &str,
+    params: Option<QueryParams>,
+    driver: DatabaseDriver,
+) -> Result<(String, Vec<QueryParam>), AppError> {
+    match params {
+        None => Ok((query.to_string(), Vec::new())),
+        Some(QueryParams::Positional(values)) => Ok((query.to_string(), values)),
+        Some(QueryParams::Named(values)) => {
+            let (query, names) = rewrite_named(query, driver);
+            let bound = names
+                .iter()
+                .map(|name| {
+                    // A name used twice is bound twice for `?` drivers
+                    values
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| AppError::Validation(format!("No value given for parameter :{}", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((query, bound))
+        }
+    }
+}
+
+/// Rewrite `:name` placeholders to `?` or `$n`, returning the binding order ✂️
+///
+/// PostgreSQL reuses the same `$n` for a repeated name, so each name is bound
+/// once; the `?` drivers bind every occurrence.
+pub fn rewrite_named(query: &str, driver: DatabaseDriver) -> (String, Vec<String>) {
+    let bytes = query.as_bytes();
+    let mysql = driver == DatabaseDriver::MySQL;
+    let postgres = driver == DatabaseDriver::PostgreSQL;
+
+    let mut rewritten = String::with_capacity(query.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'\'' => {
+                let escape_string = postgres
+                    && i > 0
+                    && bytes[i - 1].eq_ignore_ascii_case(&b'e')
+                    && (i < 2 || !is_identifier_byte(bytes[i - 2]));
+                i = skip_quoted(bytes, i, mysql || escape_string);
+            }
+            b'"' => i = skip_quoted(bytes, i, mysql),
+            b'`' if !postgres => i = skip_quoted(bytes, i, false),
+            b'-' if next == Some(b'-') => {
+                // MySQL needs whitespace after `--` - otherwise it is two minus signs
+                if mysql && !bytes.get(i + 2).map_or(true, |b| b.is_ascii_whitespace()) {
+                    i += 2;
+                } else {
+                    i = skip_line(bytes, i);
+                }
+            }
+            b'#' if mysql => i = skip_line(bytes, i),
+            b'/' if next == Some(b'*') => i = skip_block_comment(bytes, i, postgres),
+            b'$' if postgres => match dollar_tag(query, i) {
+                Some(tag) => {
+                    let body = i + tag.len();
+                    i = query[body..].find(tag).map_or(bytes.len(), |pos| body + pos + tag.len());
+                }
+                None => i += 1,
+            },
+            // `::type` casts, not a placeholder
+            b':' if next == Some(b':') => i += 2,
+            b':' if next.is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+                && (i == 0 || !is_identifier_byte(bytes[i - 1])) =>
+            {
+                let end = i + 1 + bytes[i + 1..].iter().take_while(|b| is_identifier_byte(**b) && **b != b'$').count();
+                let name = &query[i + 1..end];
+
+                rewritten.push_str(&query[copied..i]);
+                if postgres {
+                    let position = match names.iter().position(|n| n == name) {
+                        Some(position) => position,
+                        None => {
+                            names.push(name.to_string());
+                            names.len() - 1
+                        }
+                    };
+                    rewritten.push_str(&format!("${}", position + 1));
+                } else {
+                    names.push(name.to_string());
+                    rewritten.push('?');
+                }
+                copied = end;
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    rewritten.push_str(&query[copied..]);
+    (rewritten, names)
+}
+
+/// Parse a `Date` parameter into the most specific chrono type that fits
+enum DateValue {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Timestamp(DateTime<Utc>),
+}
+
+fn parse_date(value: &str) -> Result<DateValue, AppError> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(DateValue::Timestamp(ts.with_timezone(&Utc)));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(DateValue::DateTime(dt));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(DateValue::Date)
+        .map_err(|_| AppError::Validation(format!("'{}' is not a valid date or timestamp", value)))
+}
+
+/// A `Null` parameter 🕳️
+///
+/// PostgreSQL gets it without a type (OID 0), so the server infers one from
+/// where it's used - a NULL typed as `text` would break `int_column = $1`.
+/// MySQL and SQLite don't mind, and get a plain text NULL.
+pub struct UntypedNull;
+
+impl Type<Postgres> for UntypedNull {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(0))
+    }
+}
+
+impl<'q> Encode<'q, Postgres> for UntypedNull {
+    fn encode_by_ref(&self, _buf: &mut <Postgres as Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        Ok(IsNull::Yes)
+    }
+}
+
+impl Type<MySql> for UntypedNull {
+    fn type_info() -> MySqlTypeInfo {
+        <String as Type<MySql>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, MySql> for UntypedNull {
+    fn encode_by_ref(&self, _buf: &mut <MySql as Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        Ok(IsNull::Yes)
+    }
+}
+
+impl Type<Sqlite> for UntypedNull {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for UntypedNull {
+    fn encode_by_ref(&self, _buf: &mut <Sqlite as Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        Ok(IsNull::Yes)
+    }
+}
+
+/// Bind parameters to a query, in order! 🔗
+///
+/// # Errors
+/// Returns `AppError::Validation` for a `Date` value that doesn't parse.
+pub fn bind_params<'q, DB>(
+    mut query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    params: &[QueryParam],
+) -> Result<Query<'q, DB, <DB as Database>::Arguments<'q>>, AppError>
+where
+    DB: Database,
+    String: Encode<'q, DB> + Type<DB>,
+    UntypedNull: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+    NaiveDate: Encode<'q, DB> + Type<DB>,
+    NaiveDateTime: Encode<'q, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'q, DB> + Type<DB>,
+    Json<serde_json::Value>: Encode<'q, DB> + Type<DB>,
+{
+    for param in params {
+        query = match param {
+            QueryParam::String(v) => query.bind(v.clone()),
+            QueryParam::Int(v) => query.bind(*v),
+            QueryParam::Float(v) => query.bind(*v),
+            QueryParam::Bool(v) => query.bind(*v),
+            QueryParam::Null => query.bind(UntypedNull),
+            QueryParam::Date(v) => match parse_date(v)? {
+                DateValue::Date(d) => query.bind(d),
+                DateValue::DateTime(dt) => query.bind(dt),
+                DateValue::Timestamp(ts) => query.bind(ts),
+            },
+            QueryParam::Json(v) => query.bind(Json(v.clone())),
+            QueryParam::Bytes(v) => query.bind(v.clone()),
+        };
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Row;
+
+    #[test]
+    fn test_rewrite_named_per_driver() {
+        let query = "SELECT * FROM users WHERE id = :id OR parent = :id AND name = :name";
+
+        let (pg, names) = rewrite_named(query, DatabaseDriver::PostgreSQL);
+        assert_eq!(pg, "SELECT * FROM users WHERE id = $1 OR parent = $1 AND name = $2");
+        assert_eq!(names, vec!["id", "name"]);
+
+        let (mysql, names) = rewrite_named(query, DatabaseDriver::MySQL);
+        assert_eq!(mysql, "SELECT * FROM users WHERE id = ? OR parent = ? AND name = ?");
+        assert_eq!(names, vec!["id", "id", "name"]);
+    }
+
+    #[test]
+    fn test_rewrite_named_skips_strings_comments_and_casts() {
+        let query = "SELECT ':not', $$ :body $$, created::date /* :c */ FROM t -- :x\nWHERE a = :a";
+        let (pg, names) = rewrite_named(query, DatabaseDriver::PostgreSQL);
+        assert_eq!(
+            pg,
+            "SELECT ':not', $$ :body $$, created::date /* :c */ FROM t -- :x\nWHERE a = $1"
+        );
+        assert_eq!(names, vec!["a"]);
+
+        // MySQL's := assignment stays as it is
+        let (mysql, names) = rewrite_named("SET @n := :start", DatabaseDriver::MySQL);
+        assert_eq!(mysql, "SET @n := ?");
+        assert_eq!(names, vec!["start"]);
+    }
+
+    #[test]
+    fn test_resolve_named_requires_every_value() {
+        let params = QueryParams::Named(HashMap::from([("id".to_string(), QueryParam::Int(7))]));
+        let (query, values) = resolve("SELECT :id, :id", Some(params.clone()), DatabaseDriver::SQLite).unwrap();
+        assert_eq!(query, "SELECT ?, ?");
+        assert_eq!(values, vec![QueryParam::Int(7), QueryParam::Int(7)]);
+
+        let missing = resolve("SELECT :other", Some(params), DatabaseDriver::SQLite);
+        assert!(matches!(missing, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_params_deserialize() {
+        let positional: QueryParams =
+            serde_json::from_str(r#"[{"type":"int","value":1},{"type":"null"},{"type":"bytes","value":[1,2]}]"#)
+                .unwrap();
+        assert_eq!(
+            positional,
+            QueryParams::Positional(vec![QueryParam::Int(1), QueryParam::Null, QueryParam::Bytes(vec![1, 2])])
+        );
+
+        let named: QueryParams = serde_json::from_str(r#"{"name":{"type":"string","value":"anko"}}"#).unwrap();
+        assert!(matches!(named, QueryParams::Named(values) if values["name"] == QueryParam::String("anko".into())));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert!(matches!(parse_date("2024-03-01"), Ok(DateValue::Date(_))));
+        assert!(matches!(parse_date("2024-03-01 10:30:00"), Ok(DateValue::DateTime(_))));
+        assert!(matches!(parse_date("2024-03-01T10:30:00+02:00"), Ok(DateValue::Timestamp(_))));
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind_params_with_sqlite() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let params = vec![
+            QueryParam::String("it's".to_string()),
+            QueryParam::Int(42),
+            QueryParam::Null,
+            QueryParam::Bool(true),
+        ];
+
+        let query = bind_params(sqlx::query("SELECT ?, ?, ? IS NULL, ?"), &params).unwrap();
+        let row = query.fetch_one(&pool).await.unwrap();
+        assert_eq!(row.get::<String, _>(0), "it's");
+        assert_eq!(row.get::<i64, _>(1), 42);
+        assert!(row.get::<bool, _>(2));
+        assert!(row.get::<bool, _>(3));
+    }
+}
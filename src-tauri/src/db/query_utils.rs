@@ -1,5 +1,123 @@
 //! SQL query parsing utilities shared across database connectors
 
+/// Rough classification of a SQL statement by its leading keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    /// Returns rows (SELECT, WITH, SHOW, EXPLAIN, ...)
+    Query,
+    /// Changes rows (INSERT, UPDATE, DELETE, ...)
+    Modification,
+    /// Anything else (DDL, SET, USE, ...)
+    Other,
+}
+
+/// How a statement changes the transaction of the session it runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionEffect {
+    /// Opens a transaction (BEGIN, START TRANSACTION, COMMIT AND CHAIN)
+    Begin,
+    /// Ends the transaction (COMMIT, ROLLBACK, END, ABORT)
+    End,
+    /// Leaves the transaction as it is
+    None,
+}
+
+/// Skip leading whitespace, `--`/`#` line comments and `/* */` block comments.
+pub fn skip_leading_comments(query: &str) -> &str {
+    let mut rest = query.trim_start();
+    loop {
+        if rest.starts_with("--") || rest.starts_with('#') {
+            rest = rest.find('\n').map_or("", |pos| &rest[pos + 1..]).trim_start();
+        } else if rest.starts_with("/*") {
+            rest = rest.find("*/").map_or("", |pos| &rest[pos + 2..]).trim_start();
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// First keyword of a statement, uppercased (ignores comments and opening parentheses).
+pub fn leading_keyword(query: &str) -> String {
+    skip_leading_comments(query)
+        .trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// First `count` words of a statement, uppercased (ignores comments and punctuation).
+pub fn leading_words(query: &str, count: usize) -> Vec<String> {
+    skip_leading_comments(query)
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .take(count)
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// Classify a statement by its leading keyword.
+///
+/// `WITH ... INSERT` counts as a query here; callers that care about affected
+/// rows should still look at what the server reported.
+pub fn statement_kind(query: &str) -> StatementKind {
+    match leading_keyword(query).as_str() {
+        "SELECT" | "WITH" | "SHOW" | "EXPLAIN" | "DESCRIBE" | "DESC" | "VALUES" | "TABLE" | "PRAGMA" => {
+            StatementKind::Query
+        }
+        "INSERT" | "UPDATE" | "DELETE" | "REPLACE" | "MERGE" | "UPSERT" => StatementKind::Modification,
+        _ => StatementKind::Other,
+    }
+}
+
+/// Detect transaction control statements.
+///
+/// `ROLLBACK TO SAVEPOINT` keeps the transaction open, `... AND CHAIN` starts
+/// a new one right away. Implicit commits (MySQL DDL) are up to the caller.
+pub fn transaction_effect(query: &str) -> TransactionEffect {
+    let words = leading_words(query, 5);
+    let has = |word: &str| words.iter().any(|w| w == word);
+
+    match words.first().map(String::as_str) {
+        Some("BEGIN") => TransactionEffect::Begin,
+        Some("START") if words.get(1).is_some_and(|w| w == "TRANSACTION") => TransactionEffect::Begin,
+        Some("COMMIT" | "END" | "ROLLBACK" | "ABORT") => {
+            if has("TO") {
+                TransactionEffect::None
+            } else if has("CHAIN") && !has("NO") {
+                TransactionEffect::Begin
+            } else {
+                TransactionEffect::End
+            }
+        }
+        _ => TransactionEffect::None,
+    }
+}
+
+/// Whether a statement only reads, so stopping it halfway loses nothing.
+///
+/// `WITH` only counts without data-modifying words anywhere in it: stopping
+/// `WITH gone AS (DELETE ... RETURNING *) SELECT ...` would roll the delete back.
+pub fn only_reads(query: &str) -> bool {
+    match leading_keyword(query).as_str() {
+        "SELECT" | "SHOW" | "VALUES" | "TABLE" | "DESCRIBE" | "DESC" | "PRAGMA" => true,
+        "WITH" => !leading_words(query, usize::MAX)
+            .iter()
+            .any(|word| matches!(word.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE")),
+        _ => false,
+    }
+}
+
+/// Whether a statement sets or resets the session variable `name`.
+///
+/// Covers `SET name`, `SET SESSION/LOCAL name`, `SET @@session.name` and
+/// `RESET name`, case-insensitively.
+pub fn sets_variable(query: &str, name: &str) -> bool {
+    let words = leading_words(query, 4);
+    matches!(words.first().map(String::as_str), Some("SET" | "RESET"))
+        && words.iter().skip(1).any(|word| word.eq_ignore_ascii_case(name))
+}
+
 /// Extract table name from a simple SELECT query.
 ///
 /// Handles various SQL patterns:
@@ -62,6 +180,42 @@ pub fn extract_table_from_select(query: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_statement_kind() {
+        assert_eq!(statement_kind("SELECT 1"), StatementKind::Query);
+        assert_eq!(statement_kind("  with x as (select 1) select * from x"), StatementKind::Query);
+        assert_eq!(statement_kind("(SELECT 1) UNION (SELECT 2)"), StatementKind::Query);
+        assert_eq!(statement_kind("insert into t values (1)"), StatementKind::Modification);
+        assert_eq!(statement_kind("-- note\n/* block */ DELETE FROM t"), StatementKind::Modification);
+        assert_eq!(statement_kind("# mysql comment\nUPDATE t SET a = 1"), StatementKind::Modification);
+        assert_eq!(statement_kind("CREATE TABLE t (id int)"), StatementKind::Other);
+        assert_eq!(statement_kind(""), StatementKind::Other);
+    }
+
+    #[test]
+    fn test_transaction_effect() {
+        assert_eq!(transaction_effect("BEGIN"), TransactionEffect::Begin);
+        assert_eq!(transaction_effect("start transaction read only"), TransactionEffect::Begin);
+        assert_eq!(transaction_effect("START REPLICA"), TransactionEffect::None);
+        assert_eq!(transaction_effect("commit;"), TransactionEffect::End);
+        assert_eq!(transaction_effect("-- done\nROLLBACK WORK"), TransactionEffect::End);
+        assert_eq!(transaction_effect("END"), TransactionEffect::End);
+        assert_eq!(transaction_effect("ROLLBACK TO SAVEPOINT before_import"), TransactionEffect::None);
+        assert_eq!(transaction_effect("COMMIT AND CHAIN"), TransactionEffect::Begin);
+        assert_eq!(transaction_effect("COMMIT AND NO CHAIN"), TransactionEffect::End);
+        assert_eq!(transaction_effect("SELECT 'BEGIN'"), TransactionEffect::None);
+    }
+
+    #[test]
+    fn test_only_reads() {
+        assert!(only_reads("SELECT * FROM big"));
+        assert!(only_reads("-- peek\nwith x as (select 1) select * from x"));
+        assert!(!only_reads("WITH gone AS (DELETE FROM t RETURNING *) SELECT * FROM gone"));
+        assert!(!only_reads("INSERT INTO t SELECT * FROM s RETURNING id"));
+        assert!(!only_reads("EXPLAIN ANALYZE DELETE FROM t"));
+        assert!(!only_reads("CALL refresh_all()"));
+    }
+
     #[test]
     fn test_extract_simple_table() {
         assert_eq!(
@@ -106,6 +260,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sets_variable() {
+        assert!(sets_variable("SET statement_timeout = '5min'", "statement_timeout"));
+        assert!(sets_variable("set local Statement_Timeout to 0", "statement_timeout"));
+        assert!(sets_variable("RESET statement_timeout", "statement_timeout"));
+        assert!(sets_variable("SET @@session.max_execution_time = 500", "max_execution_time"));
+        assert!(sets_variable("/* tune */ SET SESSION max_execution_time = 500", "max_execution_time"));
+        assert!(!sets_variable("SET search_path TO app", "statement_timeout"));
+        assert!(!sets_variable("SELECT current_setting('statement_timeout')", "statement_timeout"));
+    }
+
     #[test]
     fn test_extract_invalid_queries() {
         assert_eq!(extract_table_from_select("INSERT INTO users"), None);
@@ -0,0 +1,422 @@
+//! Multi-statement script support! 📜✨
+//!
+//! `sqlx::query` runs a single prepared statement, so a pasted migration with
+//! several `;`-separated statements has to be split first. The splitter knows
+//! enough of each dialect to leave semicolons inside these alone:
+//!
+//! - Quoted strings and identifiers (`'...'`, `"..."`, `` `...` ``, `E'...'`)
+//! - Comments (`--`, `/* */`, and `#` for MySQL)
+//! - PostgreSQL dollar quoting (`$$ ... $$`, `$body$ ... $body$`)
+//! - MySQL `DELIMITER` lines, as used around stored procedures
+//! - SQLite `CREATE TRIGGER ... BEGIN ... END` bodies
+//!
+//! The statements then run one by one on a single session, each getting its
+//! own `QueryResult`~ 🎯
+
+use std::time::Instant;
+
+use uuid::Uuid;
+
+use super::connector::{DatabaseConnector, DatabaseDriver, QueryResult};
+use super::query_utils::{leading_words, skip_leading_comments};
+use crate::error::{scrub_open_secrets, AppError};
+
+/// Split a script into statements, dialect-aware! ✂️
+///
+/// Statements are trimmed and come without their delimiter. Chunks holding
+/// nothing but whitespace and comments are dropped.
+pub fn split_statements(script: &str, driver: DatabaseDriver) -> Vec<String> {
+    let bytes = script.as_bytes();
+    let mysql = driver == DatabaseDriver::MySQL;
+    let postgres = driver == DatabaseDriver::PostgreSQL;
+    let sqlite = driver == DatabaseDriver::SQLite;
+
+    let mut statements = Vec::new();
+    let mut delimiter = ";".to_string();
+    // Open BEGIN/CASE blocks of a SQLite trigger body
+    let mut block_depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+
+        // DELIMITER is a client command: it only changes how we split
+        if mysql && is_delimiter_command(script, start, i) {
+            let line_end = script[i..].find('\n').map_or(script.len(), |pos| i + pos);
+            let new_delimiter = script[i + "DELIMITER".len()..line_end].trim();
+            if !new_delimiter.is_empty() {
+                delimiter = new_delimiter.to_string();
+            }
+            i = line_end;
+            start = i;
+            continue;
+        }
+
+        match bytes[i] {
+            b'\'' => {
+                // E'...' strings take backslash escapes in PostgreSQL
+                let escape_string = postgres
+                    && i > 0
+                    && bytes[i - 1].eq_ignore_ascii_case(&b'e')
+                    && (i < 2 || !is_identifier_byte(bytes[i - 2]));
+                i = skip_quoted(bytes, i, mysql || escape_string);
+            }
+            b'"' => i = skip_quoted(bytes, i, mysql),
+            b'`' if !postgres => i = skip_quoted(bytes, i, false),
+            b'-' if next == Some(b'-') => {
+                // MySQL needs whitespace after `--` - otherwise it is two minus signs
+                if mysql && !bytes.get(i + 2).map_or(true, |b| b.is_ascii_whitespace()) {
+                    i += 2;
+                } else {
+                    i = skip_line(bytes, i);
+                }
+            }
+            b'#' if mysql => i = skip_line(bytes, i),
+            b'/' if next == Some(b'*') => i = skip_block_comment(bytes, i, postgres),
+            b'$' if postgres => match dollar_tag(script, i) {
+                Some(tag) => {
+                    let body = i + tag.len();
+                    i = script[body..].find(tag).map_or(bytes.len(), |pos| body + pos + tag.len());
+                }
+                None => i += 1,
+            },
+            _ if block_depth == 0 && bytes[i..].starts_with(delimiter.as_bytes()) => {
+                push_statement(&mut statements, &script[start..i]);
+                i += delimiter.len();
+                start = i;
+            }
+            b if sqlite && b.is_ascii_alphabetic() && (i == 0 || !is_identifier_byte(bytes[i - 1])) => {
+                let end = i + bytes[i..].iter().take_while(|b| is_identifier_byte(**b)).count();
+                let word = script[i..end].to_ascii_uppercase();
+                match word.as_str() {
+                    "BEGIN" if block_depth > 0 || is_trigger(&script[start..i]) => block_depth += 1,
+                    "CASE" if block_depth > 0 => block_depth += 1,
+                    "END" if block_depth > 0 => block_depth -= 1,
+                    _ => {}
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    push_statement(&mut statements, &script[start..]);
+    statements
+}
+
+/// Run a script statement by statement and collect every result! 🚀
+///
+/// The first statement gets `context` applied (MySQL `USE`, PostgreSQL
+/// `search_path`); later ones run wherever the script itself left the
+/// session, so a `USE other_db;` inside the script sticks. Without a
+/// `session_id`, a throwaway session keeps the whole script on one connection.
+///
+/// Failed statements show up as results with `error` set. With
+/// `stop_on_error`, the script ends at the first failure; a cancelled
+/// statement always ends it.
+///
+/// # Errors
+/// Only fails as a whole when the throwaway session can't be released.
+pub async fn execute_script(
+    connector: &dyn DatabaseConnector,
+    script: &str,
+    database: Option<&str>,
+    context: Option<&str>,
+    session_id: Option<&str>,
+    stop_on_error: bool,
+) -> Result<Vec<QueryResult>, AppError> {
+    let statements = split_statements(script, connector.driver());
+
+    let temporary_session = session_id.is_none().then(|| format!("script-{}", Uuid::new_v4()));
+    let session_id = session_id.or(temporary_session.as_deref());
+
+    let mut results = Vec::with_capacity(statements.len());
+    for (index, statement) in statements.iter().enumerate() {
+        let context = if index == 0 { context } else { None };
+        let start = Instant::now();
+
+        match connector.execute_with_context(statement, database, context, session_id).await {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                let cancelled = matches!(e, AppError::QueryCancelled);
+                results.push(QueryResult {
+                    columns: vec![],
+                    rows: vec![],
+                    affected_rows: 0,
+                    execution_time_ms: start.elapsed().as_millis() as u64,
+                    original_query: Some(statement.clone()),
+                    executed_query: None,
+                    transaction_state: session_id
+                        .map(|id| connector.transaction_state(id))
+                        .unwrap_or_default(),
+                    // Goes to the frontend and into history as is, so scrub it like AppError
+                    error: Some(scrub_open_secrets(&e.to_string())),
+                    truncated: false,
+                });
+                if stop_on_error || cancelled {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(id) = &temporary_session {
+        connector.close_session(id).await?;
+    }
+    Ok(results)
+}
+
+fn push_statement(statements: &mut Vec<String>, chunk: &str) {
+    if !skip_leading_comments(chunk).is_empty() {
+        statements.push(chunk.trim().to_string());
+    }
+}
+
+pub fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+/// A `DELIMITER` command must open its own line, before anything but comments
+fn is_delimiter_command(script: &str, start: usize, i: usize) -> bool {
+    let rest = &script.as_bytes()[i..];
+    rest.len() > "DELIMITER".len()
+        && rest[.."DELIMITER".len()].eq_ignore_ascii_case(b"DELIMITER")
+        && rest["DELIMITER".len()].is_ascii_whitespace()
+        && skip_leading_comments(&script[start..i]).is_empty()
+        && script[..i].rsplit('\n').next().is_some_and(|line| line.trim().is_empty())
+}
+
+/// Whether a statement (so far) is `CREATE [TEMP] TRIGGER ...`
+fn is_trigger(statement: &str) -> bool {
+    let words = leading_words(statement, 3);
+    words.first().is_some_and(|w| w == "CREATE") && words.iter().any(|w| w == "TRIGGER")
+}
+
+/// Index right after the quoted string/identifier starting at `start`
+pub fn skip_quoted(bytes: &[u8], start: usize, backslash_escapes: bool) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            // A doubled quote is an escaped quote
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Index of the newline ending the line comment at `start` (or the end)
+pub fn skip_line(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |pos| start + pos)
+}
+
+/// Index right after the block comment at `start` (PostgreSQL nests them)
+pub fn skip_block_comment(bytes: &[u8], start: usize, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'/' && bytes[i + 1] == b'*' && (nested || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes[i + 1] == b'/' {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// The `$tag$` opening a dollar-quoted string at `start`, if there is one.
+///
+/// `$1` parameters and identifiers containing `$` are not tags.
+pub fn dollar_tag(script: &str, start: usize) -> Option<&str> {
+    let bytes = script.as_bytes();
+    if start > 0 && is_identifier_byte(bytes[start - 1]) {
+        return None;
+    }
+
+    let name_len = bytes[start + 1..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    let name = &bytes[start + 1..start + 1 + name_len];
+    if name.first().is_some_and(|b| b.is_ascii_digit()) || bytes.get(start + 1 + name_len) != Some(&b'$') {
+        return None;
+    }
+    Some(&script[start..start + name_len + 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::SqliteConnector;
+    use crate::db::tls::TlsConfig;
+    use std::collections::BTreeMap;
+    use crate::db::{ConnectionConfig, QueryLimits};
+    use tempfile::{tempdir, TempDir};
+
+    async fn create_sqlite_connector() -> (TempDir, SqliteConnector) {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("script.db");
+        std::fs::File::create(&path).unwrap();
+        let config = ConnectionConfig {
+            name: "test".to_string(),
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database: None,
+            driver: DatabaseDriver::SQLite,
+            file_path: Some(path.display().to_string()),
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        };
+        let connector = SqliteConnector::connect(&config).await.unwrap();
+        (temp_dir, connector)
+    }
+
+    #[test]
+    fn test_split_simple_statements() {
+        let statements = split_statements(
+            "CREATE TABLE t (id int);\n\nINSERT INTO t VALUES (1);  -- first row\nSELECT * FROM t",
+            DatabaseDriver::PostgreSQL,
+        );
+        assert_eq!(
+            statements,
+            vec!["CREATE TABLE t (id int)", "INSERT INTO t VALUES (1)", "-- first row\nSELECT * FROM t"]
+        );
+    }
+
+    #[test]
+    fn test_split_ignores_semicolons_in_strings_and_comments() {
+        let statements = split_statements(
+            "INSERT INTO t VALUES ('a;b', 'it''s; fine'); /* x; y */ SELECT \"odd;name\" FROM t; -- end;",
+            DatabaseDriver::PostgreSQL,
+        );
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "INSERT INTO t VALUES ('a;b', 'it''s; fine')");
+        assert_eq!(statements[1], "/* x; y */ SELECT \"odd;name\" FROM t");
+    }
+
+    #[test]
+    fn test_split_postgres_dollar_quotes() {
+        let script = "CREATE FUNCTION one() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql;\n\
+                      DO $body$ BEGIN PERFORM 1; END $body$;\n\
+                      SELECT $1::text, E'it\\'s;'";
+        let statements = split_statements(script, DatabaseDriver::PostgreSQL);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("LANGUAGE plpgsql"));
+        assert!(statements[1].starts_with("DO $body$"));
+        assert_eq!(statements[2], "SELECT $1::text, E'it\\'s;'");
+    }
+
+    #[test]
+    fn test_split_postgres_nested_block_comments() {
+        let statements = split_statements("/* outer /* inner; */ still; */ SELECT 1; SELECT 2", DatabaseDriver::PostgreSQL);
+        assert_eq!(statements, vec!["/* outer /* inner; */ still; */ SELECT 1", "SELECT 2"]);
+    }
+
+    #[test]
+    fn test_split_mysql_delimiter() {
+        let script = "DROP PROCEDURE IF EXISTS p;\n\
+                      DELIMITER $$\n\
+                      CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END$$\n\
+                      DELIMITER ;\n\
+                      CALL p();";
+        let statements = split_statements(script, DatabaseDriver::MySQL);
+        assert_eq!(
+            statements,
+            vec![
+                "DROP PROCEDURE IF EXISTS p",
+                "CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END",
+                "CALL p()",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_mysql_comments_and_escapes() {
+        let script = "# setup\nINSERT INTO t VALUES ('a\\';b', `we;ird`); SELECT 5--1;";
+        let statements = split_statements(script, DatabaseDriver::MySQL);
+        assert_eq!(statements, vec!["# setup\nINSERT INTO t VALUES ('a\\';b', `we;ird`)", "SELECT 5--1"]);
+    }
+
+    #[test]
+    fn test_split_sqlite_trigger_body() {
+        let script = "CREATE TRIGGER audit AFTER INSERT ON t BEGIN\n\
+                        INSERT INTO log VALUES (CASE WHEN new.id > 0 THEN 'pos' ELSE 'neg' END);\n\
+                        UPDATE counters SET n = n + 1;\n\
+                      END;\n\
+                      BEGIN TRANSACTION; INSERT INTO t VALUES (1); COMMIT;";
+        let statements = split_statements(script, DatabaseDriver::SQLite);
+        assert_eq!(statements.len(), 4);
+        assert!(statements[0].starts_with("CREATE TRIGGER") && statements[0].ends_with("END"));
+        assert_eq!(&statements[1..], ["BEGIN TRANSACTION", "INSERT INTO t VALUES (1)", "COMMIT"]);
+    }
+
+    #[test]
+    fn test_split_drops_empty_chunks() {
+        assert!(split_statements(" ;; -- only a comment\n", DatabaseDriver::PostgreSQL).is_empty());
+        assert!(split_statements("", DatabaseDriver::MySQL).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_results_per_statement() {
+        let (_dir, connector) = create_sqlite_connector().await;
+        let script = "CREATE TABLE t (id INTEGER);\nINSERT INTO t VALUES (1), (2);\nSELECT * FROM t;";
+
+        let results = execute_script(&connector, script, None, None, None, true).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1].affected_rows, 2);
+        assert_eq!(results[2].rows.len(), 2);
+        assert!(results.iter().all(|r| r.error.is_none()));
+        assert_eq!(results[2].original_query.as_deref(), Some("SELECT * FROM t"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_stop_or_continue_on_error() {
+        let (_dir, connector) = create_sqlite_connector().await;
+        let script = "CREATE TABLE t (id INTEGER); INSERT INTO missing VALUES (1); INSERT INTO t VALUES (1);";
+
+        let stopped = execute_script(&connector, script, None, None, None, true).await.unwrap();
+        assert_eq!(stopped.len(), 2);
+        assert!(stopped[1].error.as_deref().unwrap().contains("no such table: missing"));
+
+        connector.execute("DROP TABLE t").await.unwrap();
+        let continued = execute_script(&connector, script, None, None, None, false).await.unwrap();
+        assert_eq!(continued.len(), 3);
+        assert!(continued[1].error.is_some());
+        assert_eq!(continued[2].affected_rows, 1);
+        assert!(continued[2].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_scrubs_statement_errors() {
+        let (_dir, connector) = create_sqlite_connector().await;
+        crate::error::remember_secrets("script-scrub-test", &["hunter42"]);
+
+        let results = execute_script(&connector, "SELECT * FROM hunter42_backup;", None, None, None, true)
+            .await
+            .unwrap();
+        crate::error::forget_secrets("script-scrub-test");
+
+        let error = results[0].error.as_deref().unwrap();
+        assert!(error.contains("no such table: ***_backup"), "{}", error);
+    }
+}
@@ -0,0 +1,344 @@
+//! Dedicated connections for editor sessions! 📌✨
+//!
+//! A pool hands out whichever connection happens to be free, so `SET
+//! search_path`, `USE db`, session variables and temp tables created by one
+//! query could be gone by the next. Instead, every editor session (usually a
+//! tab) owns one connection acquired from the pool and keeps it until the tab
+//! is closed, the connection breaks, or the whole connection is disconnected~
+//!
+//! Closed sessions are detached from their pool rather than returned to it,
+//! so their session state never leaks into schema browsing queries. 🧹
+//!
+//! Sessions also remember whether they hold a transaction open, so manual
+//! BEGIN / COMMIT / ROLLBACK works and disconnecting can warn about it~
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, Database};
+use tokio::sync::{MappedMutexGuard, MutexGuard};
+
+use super::connector::TransactionState;
+use super::query_utils::TransactionEffect;
+use crate::error::AppError;
+
+/// Maximum number of sessions per connection.
+///
+/// Kept below the pools' `max_connections` (10) so metadata queries always
+/// find a free connection, no matter how many tabs are open.
+pub const MAX_SESSIONS: usize = 8;
+
+/// One editor session and the connection pinned to it.
+pub struct Session<DB: Database> {
+    /// Database the connection belongs to (PostgreSQL pools are per database)
+    database: String,
+    /// Server-side id of the connection (`pg_backend_pid()` / `CONNECTION_ID()`)
+    backend_id: i64,
+    /// The pinned connection - `None` once the session has been closed
+    conn: tokio::sync::Mutex<Option<PoolConnection<DB>>>,
+    /// Readable while a query holds `conn`, e.g. when disconnecting
+    transaction: Mutex<TransactionState>,
+}
+
+impl<DB: Database> Session<DB> {
+    pub fn new(conn: PoolConnection<DB>, database: &str, backend_id: i64) -> Self {
+        Self {
+            database: database.to_string(),
+            backend_id,
+            conn: tokio::sync::Mutex::new(Some(conn)),
+            transaction: Mutex::new(TransactionState::Idle),
+        }
+    }
+
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+
+    pub fn backend_id(&self) -> i64 {
+        self.backend_id
+    }
+
+    pub fn transaction_state(&self) -> TransactionState {
+        *self.transaction.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Update the transaction state after a statement ran in this session.
+    ///
+    /// `aborts_on_error` is true for PostgreSQL, where any error inside a
+    /// transaction leaves it failed until ROLLBACK.
+    pub fn record_statement(&self, effect: TransactionEffect, succeeded: bool, aborts_on_error: bool) -> TransactionState {
+        let mut state = self.transaction.lock().unwrap_or_else(|e| e.into_inner());
+        *state = match (effect, succeeded) {
+            (TransactionEffect::Begin, true) => TransactionState::InTransaction,
+            // Even a failed COMMIT ends the transaction - the server rolls it back
+            (TransactionEffect::End, _) => TransactionState::Idle,
+            (_, false) if aborts_on_error && *state == TransactionState::InTransaction => TransactionState::Failed,
+            _ => *state,
+        };
+        *state
+    }
+
+    /// Lock the pinned connection for one query! 🔒
+    ///
+    /// Queries of the same session run one after another, in order.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session was closed meanwhile.
+    pub async fn connection(&self) -> Result<MappedMutexGuard<'_, PoolConnection<DB>>, AppError> {
+        MutexGuard::try_map(self.conn.lock().await, |conn| conn.as_mut())
+            .map_err(|_| AppError::Validation("The editor session was closed".to_string()))
+    }
+
+    /// Close the pinned connection, waiting for a running query to finish first.
+    pub async fn close(&self) {
+        let conn = self.conn.lock().await.take();
+        if let Some(conn) = conn {
+            // Closing errors only mean the connection was already gone
+            let _ = conn.detach().close().await;
+        }
+    }
+}
+
+impl<DB: Database> Drop for Session<DB> {
+    fn drop(&mut self) {
+        // Never hand a connection with leftover session state back to the pool
+        if let Some(conn) = self.conn.get_mut().take() {
+            drop(conn.detach());
+        }
+    }
+}
+
+/// Sessions of one connection, keyed by session id.
+pub struct SessionRegistry<DB: Database> {
+    sessions: Mutex<HashMap<String, Arc<Session<DB>>>>,
+}
+
+impl<DB: Database> Default for SessionRegistry<DB> {
+    fn default() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<DB: Database> SessionRegistry<DB> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Arc<Session<DB>>> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.get(session_id).cloned()
+    }
+
+    /// Register a new session, or return the one that won a concurrent race.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` when `MAX_SESSIONS` sessions are already open.
+    pub fn insert(&self, session_id: &str, session: Session<DB>) -> Result<Arc<Session<DB>>, AppError> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = sessions.get(session_id) {
+            return Ok(existing.clone());
+        }
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(AppError::Validation(format!(
+                "Too many open editor sessions on this connection (max {}). Close a tab and try again",
+                MAX_SESSIONS
+            )));
+        }
+
+        let session = Arc::new(session);
+        sessions.insert(session_id.to_string(), session.clone());
+        Ok(session)
+    }
+
+    /// Transaction state of a session (`Idle` for unknown sessions)
+    pub fn transaction_state(&self, session_id: &str) -> TransactionState {
+        self.get(session_id)
+            .map_or(TransactionState::Idle, |session| session.transaction_state())
+    }
+
+    /// Ids of sessions with an open (or failed) transaction, sorted
+    pub fn open_transactions(&self) -> Vec<String> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut open: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| session.transaction_state() != TransactionState::Idle)
+            .map(|(id, _)| id.clone())
+            .collect();
+        open.sort();
+        open
+    }
+
+    /// Get a session that has a transaction to commit or roll back.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session has no open transaction.
+    pub fn with_open_transaction(&self, session_id: &str) -> Result<Arc<Session<DB>>, AppError> {
+        self.get(session_id)
+            .filter(|session| session.transaction_state() != TransactionState::Idle)
+            .ok_or_else(|| AppError::Validation("No transaction is open in this editor session".to_string()))
+    }
+
+    /// Make sure a session is free to BEGIN.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the session already has a transaction.
+    pub fn ensure_no_transaction(&self, session_id: &str) -> Result<(), AppError> {
+        if self.transaction_state(session_id) == TransactionState::Idle {
+            Ok(())
+        } else {
+            Err(AppError::Validation(
+                "A transaction is already open in this editor session".to_string(),
+            ))
+        }
+    }
+
+    /// Unregister a session. The caller decides whether to `close` it.
+    pub fn remove(&self, session_id: &str) -> Option<Arc<Session<DB>>> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.remove(session_id)
+    }
+
+    /// Unregister `session` only if it is still the one stored for `session_id`.
+    pub fn remove_if_same(&self, session_id: &str, session: &Arc<Session<DB>>) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if sessions.get(session_id).is_some_and(|current| Arc::ptr_eq(current, session)) {
+            sessions.remove(session_id);
+        }
+    }
+
+    /// Unregister every session, e.g. right before the pools are closed.
+    pub fn drain(&self) -> Vec<Arc<Session<DB>>> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.drain().map(|(_, session)| session).collect()
+    }
+}
+
+/// Whether an error means the session's connection itself is unusable.
+///
+/// Such sessions are dropped so the next query starts on a fresh connection.
+pub fn is_connection_lost(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::Database(
+            sqlx::Error::Io(_) | sqlx::Error::Protocol(_) | sqlx::Error::Tls(_) | sqlx::Error::WorkerCrashed
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::{Executor, Sqlite, SqlitePool};
+
+    async fn create_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(MAX_SESSIONS as u32 + 2)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_keeps_its_connection() {
+        let pool = create_pool().await;
+        let registry = SessionRegistry::<Sqlite>::new();
+        let session = registry
+            .insert("tab-1", Session::new(pool.acquire().await.unwrap(), "main", 1))
+            .unwrap();
+
+        // Temp tables only exist on the connection that created them
+        session.connection().await.unwrap().execute("CREATE TEMP TABLE scratch (n INTEGER)").await.unwrap();
+        let same = registry.get("tab-1").unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM temp.scratch")
+            .fetch_one(&mut **same.connection().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_insert_respects_limit_and_races() {
+        let pool = create_pool().await;
+        let registry = SessionRegistry::<Sqlite>::new();
+
+        for i in 0..MAX_SESSIONS {
+            let conn = pool.acquire().await.unwrap();
+            registry.insert(&format!("tab-{}", i), Session::new(conn, "main", i as i64)).unwrap();
+        }
+
+        // A racing insert for an existing id returns the registered session
+        let conn = pool.acquire().await.unwrap();
+        let existing = registry.insert("tab-0", Session::new(conn, "main", 99)).unwrap();
+        assert_eq!(existing.backend_id(), 0);
+
+        let conn = pool.acquire().await.unwrap();
+        let result = registry.insert("one-too-many", Session::new(conn, "main", 100));
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_closed_session_leaves_the_pool() {
+        let pool = create_pool().await;
+        let registry = SessionRegistry::<Sqlite>::new();
+        registry
+            .insert("tab-1", Session::new(pool.acquire().await.unwrap(), "main", 1))
+            .unwrap();
+        let size_before = pool.size();
+
+        let session = registry.remove("tab-1").unwrap();
+        session.close().await;
+
+        assert_eq!(pool.size(), size_before - 1);
+        assert!(session.connection().await.is_err());
+        assert!(registry.get("tab-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_state_tracking() {
+        let pool = create_pool().await;
+        let registry = SessionRegistry::<Sqlite>::new();
+        let session = registry
+            .insert("tab-1", Session::new(pool.acquire().await.unwrap(), "main", 1))
+            .unwrap();
+
+        assert_eq!(session.record_statement(TransactionEffect::Begin, true, true), TransactionState::InTransaction);
+        assert_eq!(session.record_statement(TransactionEffect::None, true, true), TransactionState::InTransaction);
+        assert_eq!(registry.open_transactions(), vec!["tab-1".to_string()]);
+        assert!(registry.ensure_no_transaction("tab-1").is_err());
+
+        // PostgreSQL-style: an error poisons the transaction until it ends
+        assert_eq!(session.record_statement(TransactionEffect::None, false, true), TransactionState::Failed);
+        assert_eq!(session.record_statement(TransactionEffect::None, true, true), TransactionState::Failed);
+        assert!(registry.with_open_transaction("tab-1").is_ok());
+        assert_eq!(session.record_statement(TransactionEffect::End, true, true), TransactionState::Idle);
+
+        // MySQL-style: errors leave the transaction usable
+        session.record_statement(TransactionEffect::Begin, true, false);
+        assert_eq!(session.record_statement(TransactionEffect::None, false, false), TransactionState::InTransaction);
+        assert_eq!(session.record_statement(TransactionEffect::End, false, false), TransactionState::Idle);
+
+        assert!(registry.open_transactions().is_empty());
+        assert!(registry.with_open_transaction("tab-1").is_err());
+        assert!(registry.with_open_transaction("unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_if_same_keeps_newer_session() {
+        let pool = create_pool().await;
+        let registry = SessionRegistry::<Sqlite>::new();
+        let old = registry
+            .insert("tab-1", Session::new(pool.acquire().await.unwrap(), "main", 1))
+            .unwrap();
+        registry.remove("tab-1");
+        registry
+            .insert("tab-1", Session::new(pool.acquire().await.unwrap(), "main", 2))
+            .unwrap();
+
+        registry.remove_if_same("tab-1", &old);
+        assert_eq!(registry.get("tab-1").unwrap().backend_id(), 2);
+    }
+}
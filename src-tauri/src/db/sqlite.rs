@@ -0,0 +1,1329 @@
+//! SQLite connector for local database files! 🪶✨
+//!
+//! This module provides the SQLite implementation of the DatabaseConnector trait.
+//! Perfect for poking at local app databases and test fixtures right next to
+//! your MySQL and PostgreSQL servers~ 💪
+//!
+//! Special features:
+//! - Opens existing database files by path (no host/port needed!)
+//! - Schema introspection via `sqlite_master` and `PRAGMA table_info`
+//! - Attached databases (`ATTACH DATABASE ...`) show up in `get_databases`
+//! - Values are converted by their runtime storage class, so dynamic typing just works! 🌟
+
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use log::{error, info};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteQueryResult, SqliteRow};
+use sqlx::{Either, Executor, Pool, Row, Sqlite, TypeInfo, ValueRef};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::connector::{
+    ColumnDetail, ColumnInfo, ConstraintInfo, ConstraintKind, DatabaseConnector, DatabaseDriver, ForeignKeyReference,
+    IndexInfo, QueryLimits, QueryResult, ReferentialAction, SchemaInfo, SchemaObject, SchemaObjectKind, TableInfo,
+    TableStats, TransactionState,
+};
+use super::params::{bind_params, QueryParam};
+use super::query_utils::{extract_table_from_select, statement_kind, StatementKind};
+use super::stream::{columns_of, forward_rows, ResultPage, RowSink, StreamOptions, StreamSummary};
+use crate::db::ConnectionConfig;
+use crate::error::AppError;
+
+/// Schema name SQLite uses for the database file that was opened
+const MAIN_DATABASE: &str = "main";
+
+/// Result code of a statement stopped by the progress handler
+const SQLITE_INTERRUPT: &str = "9";
+
+/// VM instructions between deadline checks while a timeout is set
+const PROGRESS_HANDLER_OPS: i32 = 10_000;
+
+/// Quote an identifier for SQLite (`"name"`, doubling embedded quotes).
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Map an empty database name to `main` so callers can pass `""` safely.
+fn database_or_main(database: &str) -> &str {
+    if database.is_empty() {
+        MAIN_DATABASE
+    } else {
+        database
+    }
+}
+
+/// The WHERE clause of a partial index, from its CREATE INDEX statement
+fn partial_index_predicate(sql: &str) -> Option<String> {
+    // Skip past the parenthesized column list, then expect WHERE
+    let open = sql.find('(')?;
+    let mut depth = 0;
+    let mut close = None;
+    for (i, c) in sql[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let rest = sql[close? + 1..].trim_start();
+    rest.get(..5)
+        .filter(|keyword| keyword.eq_ignore_ascii_case("WHERE"))
+        .map(|_| rest[5..].trim().to_string())
+}
+
+/// Convert a SQLite value to JSON based on its storage class! 🎯
+///
+/// SQLite is dynamically typed: a column declared `INTEGER` can happily hold
+/// text, and expressions have no declared type at all. So instead of trusting
+/// the column type, we look at what each value actually is (INTEGER, REAL,
+/// TEXT, BLOB or NULL)~
+///
+/// # Arguments
+/// * `row` - The SQLite row
+/// * `index` - Column index
+///
+/// # Returns
+/// A `serde_json::Value` for the cell! 🌟
+fn sqlite_value_to_json(row: &SqliteRow, index: usize) -> serde_json::Value {
+    let storage_class = match row.try_get_raw(index) {
+        Ok(value) if value.is_null() => return serde_json::Value::Null,
+        Ok(value) => value.type_info().name().to_uppercase(),
+        Err(_) => return serde_json::Value::Null,
+    };
+
+    match storage_class.as_str() {
+        "INTEGER" => row
+            .try_get::<i64, _>(index)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+        "REAL" => row
+            .try_get::<f64, _>(index)
+            .map(|v| {
+                serde_json::Number::from_f64(v)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null)
+            })
+            .unwrap_or(serde_json::Value::Null),
+        "BLOB" => match row.try_get::<Vec<u8>, _>(index) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => serde_json::Value::String(s),
+                Err(e) => serde_json::Value::String(format!("[binary: {} bytes]", e.as_bytes().len())),
+            },
+            Err(_) => serde_json::Value::Null,
+        },
+        // TEXT and anything else: try as string, then numbers as a fallback
+        _ => {
+            if let Ok(v) = row.try_get::<String, _>(index) {
+                return serde_json::Value::String(v);
+            }
+            if let Ok(v) = row.try_get::<i64, _>(index) {
+                return serde_json::Value::from(v);
+            }
+            if let Ok(v) = row.try_get::<f64, _>(index) {
+                return serde_json::Number::from_f64(v)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null);
+            }
+            serde_json::Value::Null
+        }
+    }
+}
+
+/// Convert a whole result row to JSON values
+fn sqlite_row_to_json(row: &SqliteRow) -> Vec<serde_json::Value> {
+    (0..row.columns().len()).map(|i| sqlite_value_to_json(row, i)).collect()
+}
+
+/// SQLite connector backed by a single-connection pool! 🪶
+///
+/// `ATTACH DATABASE` only affects the connection it runs on, so we keep exactly
+/// one connection open. That way anything you attach in the editor is visible
+/// to every following query and to the schema browser~
+pub struct SqliteConnector {
+    /// sqlx connection pool (1 connection, 10s timeout)
+    pool: Pool<Sqlite>,
+    /// Default timeout and row limit from the connection config
+    limits: QueryLimits,
+}
+
+impl SqliteConnector {
+    /// Open an existing SQLite database file! ✨
+    ///
+    /// The file must already exist - we never silently create an empty
+    /// database because of a typo in the path~ Foreign key enforcement is
+    /// switched on so edits behave like they would in your app.
+    ///
+    /// # Arguments
+    /// * `config` - Your database configuration (only `file_path` is used)
+    ///
+    /// # Returns
+    /// A `SqliteConnector` ready to execute queries! 🚀
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if no file path was provided, or
+    /// `AppError::Database` if the file can't be opened as a SQLite database.
+    pub async fn connect(config: &ConnectionConfig) -> Result<Self, AppError> {
+        let file_path = config
+            .file_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| AppError::Validation("SQLite connections require a database file path".to_string()))?;
+
+        info!("[SQLite] Opening database file: {}", file_path);
+
+        let options = SqliteConnectOptions::new()
+            .filename(file_path)
+            .create_if_missing(false)
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(10))
+            .connect_with(options)
+            .await
+            .map_err(|e| {
+                let error_msg = match &e {
+                    sqlx::Error::Database(db_err) => {
+                        format!("Failed to open SQLite database '{}': {}", file_path, db_err)
+                    }
+                    sqlx::Error::PoolTimedOut => {
+                        format!("Timeout after 10 seconds opening SQLite database '{}'", file_path)
+                    }
+                    _ => format!("Failed to open SQLite database '{}' - {}", file_path, e),
+                };
+                error!("[SQLite] {}", error_msg);
+                AppError::Database(sqlx::Error::Configuration(error_msg.into()))
+            })?;
+
+        Ok(Self {
+            pool,
+            limits: config.limits,
+        })
+    }
+
+    /// Primary key columns of a table in key order (empty for rowid-only tables)
+    async fn get_primary_key_columns(&self, database: &str, table: &str) -> Result<Vec<String>, AppError> {
+        let rows = sqlx::query(&format!(
+            "PRAGMA {}.table_info({})",
+            quote_identifier(database),
+            quote_identifier(table)
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut keys: Vec<(i64, String)> = rows
+            .iter()
+            .filter_map(|row| {
+                let position: i64 = row.try_get("pk").ok()?;
+                if position == 0 {
+                    return None;
+                }
+                Some((position, row.try_get("name").ok()?))
+            })
+            .collect();
+        keys.sort();
+        Ok(keys.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Collect unique (`UNI`) and indexed (`MUL`) columns for a table.
+    ///
+    /// Mirrors MySQL's `COLUMN_KEY` semantics: single-column unique indexes
+    /// mark a column `UNI`, and the leading column of any other index is `MUL`.
+    async fn get_index_keys(
+        &self,
+        database: &str,
+        table: &str,
+    ) -> Result<(HashSet<String>, HashSet<String>), AppError> {
+        let schema = quote_identifier(database);
+        let index_rows = sqlx::query(&format!("PRAGMA {}.index_list({})", schema, quote_identifier(table)))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut unique = HashSet::new();
+        let mut indexed = HashSet::new();
+
+        for index_row in &index_rows {
+            let index_name: String = index_row.try_get("name")?;
+            let is_unique = index_row.try_get::<i64, _>("unique")? != 0;
+
+            let column_rows = sqlx::query(&format!(
+                "PRAGMA {}.index_info({})",
+                schema,
+                quote_identifier(&index_name)
+            ))
+            .fetch_all(&self.pool)
+            .await?;
+
+            // Expression indexes report NULL column names - skip those
+            let columns: Vec<String> = column_rows
+                .iter()
+                .filter_map(|row| row.try_get::<Option<String>, _>("name").ok().flatten())
+                .collect();
+
+            if is_unique && columns.len() == 1 {
+                unique.insert(columns[0].clone());
+            } else if let Some(first) = columns.first() {
+                indexed.insert(first.clone());
+            }
+        }
+
+        Ok((unique, indexed))
+    }
+
+    /// Run a query with bound parameters and convert the result to JSON! 🎯
+    ///
+    /// SQLite has no statement timeout, so a progress handler interrupts the
+    /// statement once its deadline passes; reading stops at the row limit~
+    async fn run_query(&self, query: &str, params: &[QueryParam], limits: &QueryLimits) -> Result<QueryResult, AppError> {
+        let statement = bind_params(sqlx::query(query), params)?;
+        let mut conn = self.pool.acquire().await?;
+        if let Some(timeout_ms) = limits.timeout_ms {
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+            conn.lock_handle()
+                .await?
+                .set_progress_handler(PROGRESS_HANDLER_OPS, move || Instant::now() < deadline);
+        }
+        let start = Instant::now();
+
+        // Collect rows and affected-row counts from a single execution, so a
+        // failing statement is never re-run
+        let max_rows = limits.row_cap();
+        let collected = async {
+            let mut rows = Vec::new();
+            let mut rows_affected = 0;
+            let mut truncated = false;
+            let mut stream = (&mut *conn).fetch_many(statement);
+            while let Some(item) = stream.try_next().await? {
+                match item {
+                    Either::Left(done) => rows_affected += done.rows_affected(),
+                    Either::Right(_) if rows.len() >= max_rows => {
+                        truncated = true;
+                        break;
+                    }
+                    Either::Right(row) => rows.push(row),
+                }
+            }
+            Ok::<_, sqlx::Error>((rows, rows_affected, truncated))
+        }
+        .await;
+
+        if limits.timeout_ms.is_some() {
+            conn.lock_handle().await?.remove_progress_handler();
+        }
+        let (rows, rows_affected, truncated) = collected.map_err(|e| match (e, limits.timeout_ms) {
+            (sqlx::Error::Database(db_err), Some(timeout_ms)) if db_err.code().as_deref() == Some(SQLITE_INTERRUPT) => {
+                AppError::QueryTimeout(timeout_ms)
+            }
+            (e, _) => AppError::Database(e),
+        })?;
+        let execution_time_ms = start.elapsed().as_millis() as u64;
+
+        // Extract column info from the first row if available
+        // When there are no rows, try to get column info from PRAGMA table_info
+        let columns: Vec<ColumnInfo> = if !rows.is_empty() {
+            columns_of(&rows[0])
+        } else {
+            let trimmed = query.trim().to_uppercase();
+            if trimmed.starts_with("SELECT") {
+                if let Some(table_name) = extract_table_from_select(query) {
+                    sqlx::query(&format!("PRAGMA table_info({})", quote_identifier(&table_name)))
+                        .fetch_all(&mut *conn)
+                        .await
+                        .map(|info_rows| {
+                            info_rows
+                                .iter()
+                                .filter_map(|row| {
+                                    Some(ColumnInfo {
+                                        name: row.try_get("name").ok()?,
+                                        data_type: row.try_get("type").ok()?,
+                                        nullable: true,
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![]
+            }
+        };
+
+        let json_rows: Vec<Vec<serde_json::Value>> = rows.iter().map(sqlite_row_to_json).collect();
+
+        let affected_rows = if statement_kind(query) == StatementKind::Query {
+            0
+        } else {
+            rows_affected
+        };
+
+        Ok(QueryResult {
+            columns,
+            rows: json_rows,
+            affected_rows,
+            execution_time_ms,
+            original_query: None,
+            executed_query: None,
+            transaction_state: TransactionState::Idle,
+            error: None,
+            truncated,
+        })
+    }
+}
+
+#[async_trait]
+impl DatabaseConnector for SqliteConnector {
+    fn driver(&self) -> DatabaseDriver {
+        DatabaseDriver::SQLite
+    }
+
+    fn limits(&self) -> QueryLimits {
+        self.limits
+    }
+
+    async fn execute_with_params(
+        &self,
+        query: &str,
+        params: &[QueryParam],
+        _database: Option<&str>,
+        _context: Option<&str>,
+        _session_id: Option<&str>,
+        limits: &QueryLimits,
+    ) -> Result<QueryResult, AppError> {
+        // SQLite has no USE or search_path - attached databases are addressed
+        // with qualified names, so the query runs exactly as written
+        let mut result = self.run_query(query, params, &limits.or(self.limits)).await?;
+        result.original_query = Some(query.to_string());
+        result.executed_query = Some(query.to_string());
+        Ok(result)
+    }
+
+    async fn cancel_query(&self, _session_id: &str) -> Result<bool, AppError> {
+        Err(AppError::Validation(
+            "Cancelling running queries is not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    async fn execute_streaming(
+        &self,
+        query: &str,
+        _database: Option<&str>,
+        _context: Option<&str>,
+        _session_id: Option<&str>,
+        limits: &QueryLimits,
+        options: &StreamOptions,
+        sink: &RowSink<'_>,
+    ) -> Result<StreamSummary, AppError> {
+        // Same deadline as `run_query`, covering the time spent sending rows too
+        let timeout_ms = limits.or(self.limits).timeout_ms;
+        let mut conn = self.pool.acquire().await?;
+        if let Some(timeout_ms) = timeout_ms {
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+            conn.lock_handle()
+                .await?
+                .set_progress_handler(PROGRESS_HANDLER_OPS, move || Instant::now() < deadline);
+        }
+
+        let start = Instant::now();
+        let forwarded = forward_rows(
+            (&mut *conn).fetch_many(sqlx::query(query)),
+            options,
+            sqlite_row_to_json,
+            SqliteQueryResult::rows_affected,
+            sink,
+        )
+        .await;
+
+        if timeout_ms.is_some() {
+            conn.lock_handle().await?.remove_progress_handler();
+        }
+        let forwarded = forwarded.map_err(|e| match (e, timeout_ms) {
+            (AppError::Database(sqlx::Error::Database(db_err)), Some(timeout_ms))
+                if db_err.code().as_deref() == Some(SQLITE_INTERRUPT) =>
+            {
+                AppError::QueryTimeout(timeout_ms)
+            }
+            (e, _) => e,
+        })?;
+
+        Ok(StreamSummary {
+            row_count: forwarded.row_count,
+            affected_rows: if statement_kind(query) == StatementKind::Query {
+                0
+            } else {
+                forwarded.rows_affected
+            },
+            truncated: forwarded.truncated,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            transaction_state: TransactionState::Idle,
+        })
+    }
+
+    async fn open_cursor(
+        &self,
+        _query: &str,
+        _database: Option<&str>,
+        _context: Option<&str>,
+        _session_id: &str,
+        _page_size: usize,
+    ) -> Result<ResultPage, AppError> {
+        // Holding a statement open would block the one connection everything shares
+        Err(AppError::Validation(
+            "Paginated results are not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    async fn fetch_next_page(&self, _session_id: &str, _page_size: usize) -> Result<ResultPage, AppError> {
+        Err(AppError::Validation(
+            "Paginated results are not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    async fn close_cursor(&self, _session_id: &str) -> Result<(), AppError> {
+        // Nothing is ever open
+        Ok(())
+    }
+
+    async fn close_session(&self, _session_id: &str) -> Result<(), AppError> {
+        // The pool holds a single connection that every session already shares
+        Ok(())
+    }
+
+    async fn begin_transaction(&self, _session_id: &str, _database: Option<&str>) -> Result<TransactionState, AppError> {
+        Err(AppError::Validation(
+            "Manual transactions are not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    async fn commit_transaction(&self, _session_id: &str) -> Result<TransactionState, AppError> {
+        Err(AppError::Validation(
+            "Manual transactions are not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    async fn rollback_transaction(&self, _session_id: &str) -> Result<TransactionState, AppError> {
+        Err(AppError::Validation(
+            "Manual transactions are not supported for SQLite connections".to_string(),
+        ))
+    }
+
+    fn transaction_state(&self, _session_id: &str) -> TransactionState {
+        TransactionState::Idle
+    }
+
+    fn open_transactions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    async fn execute(&self, query: &str) -> Result<QueryResult, AppError> {
+        self.run_query(query, &[], &self.limits).await
+    }
+
+    async fn get_databases(&self) -> Result<Vec<SchemaInfo>, AppError> {
+        // main + every ATTACHed database; temp is internal so we hide it
+        let rows = sqlx::query("PRAGMA database_list")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let databases = rows
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>("name").ok())
+            .filter(|name| name != "temp")
+            .map(|name| SchemaInfo { name })
+            .collect();
+
+        Ok(databases)
+    }
+
+    async fn get_schemas(&self, _database: &str) -> Result<Vec<SchemaInfo>, AppError> {
+        // SQLite has no schemas inside a database file
+        Ok(vec![])
+    }
+
+    async fn get_tables(&self, database: &str, _schema: &str) -> Result<Vec<TableInfo>, AppError> {
+        let database = database_or_main(database);
+
+        // sqlite_master can't be parameterized by schema, so quote the identifier
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT name, type
+            FROM {}.sqlite_master
+            WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+            "#,
+            quote_identifier(database)
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let tables = rows
+            .iter()
+            .filter_map(|row| {
+                let object_type: String = row.try_get(1).ok()?;
+                Some(TableInfo {
+                    name: row.try_get(0).ok()?,
+                    schema: database.to_string(),
+                    // Match the information_schema naming used by the other drivers
+                    table_type: if object_type == "view" { "VIEW" } else { "BASE TABLE" }.to_string(),
+                    // SQLite keeps no cheap row estimate
+                    row_count: None,
+                })
+            })
+            .collect();
+
+        Ok(tables)
+    }
+
+    async fn get_columns(
+        &self,
+        database: &str,
+        _schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnDetail>, AppError> {
+        let database = database_or_main(database);
+
+        let rows = sqlx::query(&format!(
+            "PRAGMA {}.table_info({})",
+            quote_identifier(database),
+            quote_identifier(table)
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let (unique, indexed) = self.get_index_keys(database, table).await?;
+        let primary_key_count = rows
+            .iter()
+            .filter(|row| row.try_get::<i64, _>("pk").unwrap_or(0) > 0)
+            .count();
+
+        let columns = rows
+            .iter()
+            .filter_map(|row| {
+                let name: String = row.try_get("name").ok()?;
+                let data_type: String = row.try_get("type").ok()?;
+                let is_primary = row.try_get::<i64, _>("pk").unwrap_or(0) > 0;
+
+                let key = if is_primary {
+                    Some("PRI".to_string())
+                } else if unique.contains(&name) {
+                    Some("UNI".to_string())
+                } else if indexed.contains(&name) {
+                    Some("MUL".to_string())
+                } else {
+                    None
+                };
+
+                // A lone INTEGER PRIMARY KEY aliases the rowid and auto-increments
+                let extra = (is_primary && primary_key_count == 1 && data_type.eq_ignore_ascii_case("INTEGER"))
+                    .then(|| "auto_increment".to_string());
+
+                Some(ColumnDetail {
+                    nullable: row.try_get::<i64, _>("notnull").unwrap_or(0) == 0 && !is_primary,
+                    key,
+                    default_value: row.try_get::<Option<String>, _>("dflt_value").ok().flatten(),
+                    extra,
+                    name,
+                    data_type,
+                })
+            })
+            .collect();
+
+        Ok(columns)
+    }
+
+    async fn get_indexes(&self, database: &str, _schema: &str, table: &str) -> Result<Vec<IndexInfo>, AppError> {
+        let database = database_or_main(database);
+        let schema = quote_identifier(database);
+
+        let index_rows = sqlx::query(&format!("PRAGMA {}.index_list({})", schema, quote_identifier(table)))
+            .fetch_all(&self.pool)
+            .await?;
+
+        // CREATE INDEX statements carry the predicates of partial indexes
+        let statements: HashMap<String, String> = sqlx::query(&format!(
+            "SELECT name, sql FROM {}.sqlite_master WHERE type = 'index' AND tbl_name = ? AND sql IS NOT NULL",
+            schema
+        ))
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .filter_map(|row| Some((row.try_get("name").ok()?, row.try_get("sql").ok()?)))
+        .collect();
+
+        // dbstat is optional in SQLite builds - no sizes without it
+        let sizes: HashMap<String, i64> = sqlx::query("SELECT name, SUM(pgsize) FROM dbstat(?) GROUP BY name")
+            .bind(database)
+            .fetch_all(&self.pool)
+            .await
+            .map(|rows| {
+                rows.iter()
+                    .filter_map(|row| Some((row.try_get(0).ok()?, row.try_get(1).ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut indexes = Vec::new();
+        for index_row in &index_rows {
+            let name: String = index_row.try_get("name")?;
+            let partial = index_row.try_get::<i64, _>("partial").unwrap_or(0) != 0;
+
+            let column_rows = sqlx::query(&format!("PRAGMA {}.index_info({})", schema, quote_identifier(&name)))
+                .fetch_all(&self.pool)
+                .await?;
+            let columns = column_rows
+                .iter()
+                .map(|row| {
+                    row.try_get::<Option<String>, _>("name")
+                        .ok()
+                        .flatten()
+                        .unwrap_or_else(|| "(expression)".to_string())
+                })
+                .collect();
+
+            indexes.push(IndexInfo {
+                columns,
+                unique: index_row.try_get::<i64, _>("unique")? != 0,
+                primary: index_row.try_get::<String, _>("origin").is_ok_and(|origin| origin == "pk"),
+                method: "btree".to_string(),
+                predicate: partial
+                    .then(|| statements.get(&name).and_then(|sql| partial_index_predicate(sql)))
+                    .flatten(),
+                size_bytes: sizes.get(&name).copied(),
+                name,
+            });
+        }
+
+        indexes.sort_by(|a, b| b.primary.cmp(&a.primary).then_with(|| a.name.cmp(&b.name)));
+        Ok(indexes)
+    }
+
+    async fn get_constraints(&self, database: &str, _schema: &str, table: &str) -> Result<Vec<ConstraintInfo>, AppError> {
+        let database = database_or_main(database);
+        let schema = quote_identifier(database);
+
+        // SQLite doesn't name primary keys or foreign keys, so make up stable names
+        let mut constraints = Vec::new();
+        let primary_key = self.get_primary_key_columns(database, table).await?;
+        if !primary_key.is_empty() {
+            constraints.push(ConstraintInfo {
+                name: format!("pk_{}", table),
+                kind: ConstraintKind::PrimaryKey,
+                columns: primary_key,
+                references: None,
+                expression: None,
+            });
+        }
+
+        // UNIQUE constraints show up as automatic indexes with origin 'u'
+        let index_rows = sqlx::query(&format!("PRAGMA {}.index_list({})", schema, quote_identifier(table)))
+            .fetch_all(&self.pool)
+            .await?;
+        let mut unique = Vec::new();
+        for index_row in &index_rows {
+            if !index_row.try_get::<String, _>("origin").is_ok_and(|origin| origin == "u") {
+                continue;
+            }
+            let name: String = index_row.try_get("name")?;
+            let columns = sqlx::query(&format!("PRAGMA {}.index_info({})", schema, quote_identifier(&name)))
+                .fetch_all(&self.pool)
+                .await?
+                .iter()
+                .filter_map(|row| row.try_get::<Option<String>, _>("name").ok().flatten())
+                .collect();
+            unique.push(ConstraintInfo {
+                name,
+                kind: ConstraintKind::Unique,
+                columns,
+                references: None,
+                expression: None,
+            });
+        }
+        unique.sort_by(|a, b| a.name.cmp(&b.name));
+        constraints.extend(unique);
+
+        // One row per column pair, grouped by the foreign key's id
+        let fk_rows = sqlx::query(&format!("PRAGMA {}.foreign_key_list({})", schema, quote_identifier(table)))
+            .fetch_all(&self.pool)
+            .await?;
+        let mut foreign_keys: Vec<(i64, ConstraintInfo)> = Vec::new();
+        for row in &fk_rows {
+            let id: i64 = row.try_get("id")?;
+            let from: String = row.try_get("from")?;
+            let to: Option<String> = row.try_get("to")?;
+
+            let constraint = match foreign_keys.iter_mut().find(|(fk_id, _)| *fk_id == id) {
+                Some((_, constraint)) => constraint,
+                None => {
+                    let on_delete: String = row.try_get("on_delete")?;
+                    let on_update: String = row.try_get("on_update")?;
+                    foreign_keys.push((id, ConstraintInfo {
+                        name: format!("fk_{}_{}", table, id),
+                        kind: ConstraintKind::ForeignKey,
+                        columns: Vec::new(),
+                        references: Some(ForeignKeyReference {
+                            schema: database.to_string(),
+                            table: row.try_get("table")?,
+                            columns: Vec::new(),
+                            on_delete: ReferentialAction::from_sql(&on_delete),
+                            on_update: ReferentialAction::from_sql(&on_update),
+                        }),
+                        expression: None,
+                    }));
+                    &mut foreign_keys.last_mut().unwrap().1
+                }
+            };
+            constraint.columns.push(from);
+            if let (Some(references), Some(to)) = (constraint.references.as_mut(), to) {
+                references.columns.push(to);
+            }
+        }
+
+        // `REFERENCES parent` without columns points at the parent's primary key
+        for (_, constraint) in &mut foreign_keys {
+            if let Some(references) = constraint.references.as_mut() {
+                if references.columns.is_empty() {
+                    references.columns = self.get_primary_key_columns(database, &references.table).await?;
+                }
+            }
+        }
+        foreign_keys.sort_by_key(|(id, _)| *id);
+        constraints.extend(foreign_keys.into_iter().map(|(_, constraint)| constraint));
+
+        Ok(constraints)
+    }
+
+    async fn get_object_ddl(&self, database: &str, _schema: &str, name: &str) -> Result<String, AppError> {
+        let database = database_or_main(database);
+
+        // sqlite_master keeps the statements as written; automatic indexes have no sql
+        let rows = sqlx::query(&format!(
+            "SELECT type, sql FROM {}.sqlite_master \
+             WHERE tbl_name = ? AND sql IS NOT NULL AND (name = ? OR type = 'index') \
+             ORDER BY type = 'index', name",
+            quote_identifier(database)
+        ))
+        .bind(name)
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        match rows.first() {
+            Some(row) if matches!(row.try_get::<String, _>("type")?.as_str(), "table" | "view") => {}
+            _ => return Err(AppError::NotFound(format!("Table or view {}.{}", database, name))),
+        }
+
+        let statements = rows
+            .iter()
+            .map(|row| Ok(format!("{};\n", row.try_get::<String, _>("sql")?.trim_end())))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(statements.join("\n"))
+    }
+
+    async fn list_objects(&self, database: &str, _schema: &str, kind: SchemaObjectKind) -> Result<Vec<SchemaObject>, AppError> {
+        // Triggers are the only kind SQLite has
+        if kind != SchemaObjectKind::Trigger {
+            return Ok(Vec::new());
+        }
+        let database = database_or_main(database);
+
+        let rows = sqlx::query(&format!(
+            "SELECT name, tbl_name, sql FROM {}.sqlite_master WHERE type = 'trigger' ORDER BY name",
+            quote_identifier(database)
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(SchemaObject {
+                    name: row.try_get("name")?,
+                    schema: database.to_string(),
+                    kind,
+                    table: row.try_get("tbl_name")?,
+                    arguments: None,
+                    result_type: None,
+                    definition: row.try_get("sql")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_table_stats(&self, database: &str, _schema: &str, table: &str) -> Result<TableStats, AppError> {
+        let database = database_or_main(database);
+        let schema = quote_identifier(database);
+
+        let exists = sqlx::query(&format!("SELECT 1 FROM {}.sqlite_master WHERE type = 'table' AND name = ?", schema))
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Err(AppError::NotFound(format!("Table {}.{}", database, table)));
+        }
+
+        // SQLite keeps no row estimate, so count - cheap enough for a local file
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}.{}", schema, quote_identifier(table)))
+            .fetch_one(&self.pool)
+            .await?;
+
+        // dbstat is optional in SQLite builds - no sizes without it
+        let sizes = sqlx::query(&format!(
+            "SELECT \
+                 (SELECT SUM(pgsize) FROM dbstat(?1) WHERE name = ?2), \
+                 (SELECT COALESCE(SUM(d.pgsize), 0) FROM dbstat(?1) d \
+                  JOIN {}.sqlite_master m ON m.name = d.name \
+                  WHERE m.type = 'index' AND m.tbl_name = ?2)",
+            schema
+        ))
+        .bind(database)
+        .bind(table)
+        .fetch_one(&self.pool)
+        .await
+        .ok();
+        let table_bytes = sizes.as_ref().and_then(|row| row.try_get::<Option<i64>, _>(0).ok().flatten());
+        let index_bytes = sizes.as_ref().and_then(|row| row.try_get::<Option<i64>, _>(1).ok().flatten());
+
+        Ok(TableStats {
+            estimated_rows: Some(rows),
+            total_bytes: table_bytes.zip(index_bytes).map(|(data, index)| data + index),
+            table_bytes,
+            index_bytes,
+            ..TableStats::default()
+        })
+    }
+
+    async fn close(&self) -> Result<(), AppError> {
+        self.pool.close().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::params::{self, QueryParams};
+    use crate::db::tls::TlsConfig;
+    use std::collections::BTreeMap;
+    use crate::db::stream::StreamEvent;
+    use crate::db::DatabaseDriver;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn create_test_config(file_path: &std::path::Path) -> ConnectionConfig {
+        ConnectionConfig {
+            name: "test".to_string(),
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database: None,
+            driver: DatabaseDriver::SQLite,
+            file_path: Some(file_path.display().to_string()),
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        }
+    }
+
+    /// Create an empty database file (a zero-byte file is a valid SQLite database)
+    fn create_database_file(dir: &std::path::Path, name: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::File::create(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users"), "\"users\"");
+        assert_eq!(quote_identifier("we\"ird"), "\"we\"\"ird\"");
+    }
+
+    #[test]
+    fn test_database_or_main() {
+        assert_eq!(database_or_main(""), "main");
+        assert_eq!(database_or_main("fixtures"), "fixtures");
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_file_path() {
+        let mut config = create_test_config(std::path::Path::new(""));
+        config.file_path = None;
+
+        let result = SqliteConnector::connect(&config).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_does_not_create_missing_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("missing.db");
+
+        let result = SqliteConnector::connect(&create_test_config(&path)).await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_execute_and_introspect() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector
+            .execute("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE, score REAL, avatar BLOB)")
+            .await
+            .unwrap();
+        connector.execute("CREATE VIEW user_emails AS SELECT email FROM users").await.unwrap();
+
+        let inserted = connector
+            .execute("INSERT INTO users (email, score) VALUES ('a@example.com', 1.5)")
+            .await
+            .unwrap();
+        assert_eq!(inserted.affected_rows, 1);
+
+        let result = connector.execute("SELECT id, email, score, avatar FROM users").await.unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][0], serde_json::json!(1));
+        assert_eq!(result.rows[0][1], serde_json::json!("a@example.com"));
+        assert_eq!(result.rows[0][2], serde_json::json!(1.5));
+        assert_eq!(result.rows[0][3], serde_json::Value::Null);
+
+        let tables = connector.get_tables("main", "").await.unwrap();
+        let names: Vec<_> = tables.iter().map(|t| (t.name.as_str(), t.table_type.as_str())).collect();
+        assert_eq!(names, vec![("user_emails", "VIEW"), ("users", "BASE TABLE")]);
+
+        let columns = connector.get_columns("main", "", "users").await.unwrap();
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[0].key.as_deref(), Some("PRI"));
+        assert_eq!(columns[0].extra.as_deref(), Some("auto_increment"));
+        assert!(!columns[0].nullable);
+        assert_eq!(columns[1].key.as_deref(), Some("UNI"));
+        assert!(!columns[1].nullable);
+        assert!(columns[2].nullable);
+    }
+
+    #[tokio::test]
+    async fn test_get_indexes() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector
+            .execute("CREATE TABLE orders (tenant TEXT, number INTEGER, status TEXT, total REAL, PRIMARY KEY (tenant, number))")
+            .await
+            .unwrap();
+        connector
+            .execute("CREATE INDEX idx_open_orders ON orders (status, total DESC) WHERE (status <> 'closed')")
+            .await
+            .unwrap();
+        connector.execute("CREATE UNIQUE INDEX idx_lower_status ON orders (lower(status))").await.unwrap();
+
+        let indexes = connector.get_indexes("main", "", "orders").await.unwrap();
+        let names: Vec<_> = indexes.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["sqlite_autoindex_orders_1", "idx_lower_status", "idx_open_orders"]);
+
+        assert!(indexes[0].primary && indexes[0].unique);
+        assert_eq!(indexes[0].columns, vec!["tenant", "number"]);
+        assert!(indexes[0].size_bytes.is_some());
+
+        assert!(indexes[1].unique && !indexes[1].primary);
+        assert_eq!(indexes[1].columns, vec!["(expression)"]);
+        assert_eq!(indexes[1].predicate, None);
+
+        assert!(!indexes[2].unique);
+        assert_eq!(indexes[2].columns, vec!["status", "total"]);
+        assert_eq!(indexes[2].method, "btree");
+        assert_eq!(indexes[2].predicate.as_deref(), Some("(status <> 'closed')"));
+    }
+
+    #[tokio::test]
+    async fn test_get_constraints() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector
+            .execute("CREATE TABLE customers (tenant TEXT, id INTEGER, email TEXT UNIQUE, PRIMARY KEY (tenant, id))")
+            .await
+            .unwrap();
+        connector
+            .execute(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, tenant TEXT, customer_id INTEGER, \
+                 FOREIGN KEY (tenant, customer_id) REFERENCES customers ON DELETE CASCADE)",
+            )
+            .await
+            .unwrap();
+
+        let constraints = connector.get_constraints("main", "", "customers").await.unwrap();
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[0].kind, ConstraintKind::PrimaryKey);
+        assert_eq!(constraints[0].columns, vec!["tenant", "id"]);
+        assert_eq!(constraints[1].kind, ConstraintKind::Unique);
+        assert_eq!(constraints[1].columns, vec!["email"]);
+
+        let constraints = connector.get_constraints("main", "", "orders").await.unwrap();
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[0].columns, vec!["id"]);
+
+        let foreign_key = &constraints[1];
+        assert_eq!(foreign_key.kind, ConstraintKind::ForeignKey);
+        assert_eq!(foreign_key.columns, vec!["tenant", "customer_id"]);
+        let references = foreign_key.references.as_ref().unwrap();
+        assert_eq!(references.table, "customers");
+        // No referenced columns given - resolved to the parent's primary key
+        assert_eq!(references.columns, vec!["tenant", "id"]);
+        assert_eq!(references.on_delete, ReferentialAction::Cascade);
+        assert_eq!(references.on_update, ReferentialAction::NoAction);
+    }
+
+    #[tokio::test]
+    async fn test_get_object_ddl() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE)").await.unwrap();
+        connector.execute("CREATE INDEX idx_users_email ON users (email)").await.unwrap();
+        connector.execute("CREATE VIEW user_emails AS SELECT email FROM users").await.unwrap();
+
+        let ddl = connector.get_object_ddl("main", "", "users").await.unwrap();
+        assert_eq!(
+            ddl,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE);\n\n\
+             CREATE INDEX idx_users_email ON users (email);\n"
+        );
+
+        let ddl = connector.get_object_ddl("", "", "user_emails").await.unwrap();
+        assert_eq!(ddl, "CREATE VIEW user_emails AS SELECT email FROM users;\n");
+
+        let missing = connector.get_object_ddl("main", "", "nope").await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_objects() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE items (id INTEGER PRIMARY KEY, updated_at TEXT)").await.unwrap();
+        connector
+            .execute(
+                "CREATE TRIGGER touch_items AFTER UPDATE ON items BEGIN \
+                 UPDATE items SET updated_at = datetime('now') WHERE id = NEW.id; END",
+            )
+            .await
+            .unwrap();
+
+        let triggers = connector.list_objects("main", "", SchemaObjectKind::Trigger).await.unwrap();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].name, "touch_items");
+        assert_eq!(triggers[0].table.as_deref(), Some("items"));
+        assert!(triggers[0].definition.as_deref().unwrap().starts_with("CREATE TRIGGER touch_items"));
+
+        let functions = connector.list_objects("main", "", SchemaObjectKind::Function).await.unwrap();
+        assert!(functions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_table_stats() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)").await.unwrap();
+        connector.execute("CREATE INDEX idx_notes_body ON notes (body)").await.unwrap();
+        connector.execute("INSERT INTO notes (body) VALUES ('a'), ('b'), ('c')").await.unwrap();
+
+        let stats = connector.get_table_stats("main", "", "notes").await.unwrap();
+        assert_eq!(stats.estimated_rows, Some(3));
+        let (table_bytes, index_bytes) = (stats.table_bytes.unwrap(), stats.index_bytes.unwrap());
+        assert!(table_bytes > 0 && index_bytes > 0);
+        assert_eq!(stats.total_bytes, Some(table_bytes + index_bytes));
+        assert_eq!(stats.engine, None);
+
+        let missing = connector.get_table_stats("main", "", "nope").await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_partial_index_predicate() {
+        assert_eq!(
+            partial_index_predicate("CREATE INDEX i ON t (a, (b + 1))\n  where b > 0").as_deref(),
+            Some("b > 0")
+        );
+        assert_eq!(partial_index_predicate("CREATE INDEX i ON t (a)"), None);
+    }
+
+    #[tokio::test]
+    async fn test_empty_select_returns_columns() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE items (id INTEGER, label TEXT)").await.unwrap();
+
+        let result = connector.execute("SELECT * FROM items").await.unwrap();
+        assert!(result.rows.is_empty());
+        let names: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "label"]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_statement_runs_once() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE audit (n INTEGER)").await.unwrap();
+
+        // The insert succeeds before the second statement fails - it must not be replayed
+        let error = connector
+            .execute("INSERT INTO audit VALUES (1); SELECT * FROM missing_table")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("no such table: missing_table"));
+
+        let audit = connector.execute("SELECT COUNT(*) FROM audit").await.unwrap();
+        assert_eq!(audit.rows[0][0], serde_json::json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_update_reports_affected_rows() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector.execute("CREATE TABLE items (id INTEGER, done INTEGER)").await.unwrap();
+        connector
+            .execute("INSERT INTO items VALUES (1, 0), (2, 0), (3, 1)")
+            .await
+            .unwrap();
+
+        let result = connector.execute("UPDATE items SET done = 1 WHERE done = 0").await.unwrap();
+        assert_eq!(result.affected_rows, 2);
+        assert!(result.rows.is_empty());
+
+        let result = connector.execute("SELECT * FROM items").await.unwrap();
+        assert_eq!(result.affected_rows, 0);
+        assert_eq!(result.rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_named_params() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+        connector
+            .execute("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT, data BLOB)")
+            .await
+            .unwrap();
+
+        let named = QueryParams::Named(HashMap::from([
+            ("body".to_string(), QueryParam::String("'; DROP TABLE notes; --".to_string())),
+            ("data".to_string(), QueryParam::Bytes(b"raw".to_vec())),
+        ]));
+        let (query, params) = params::resolve(
+            "INSERT INTO notes (body, data) VALUES (:body, :data)",
+            Some(named),
+            DatabaseDriver::SQLite,
+        )
+        .unwrap();
+        let inserted = connector
+            .execute_with_params(&query, &params, None, None, None, &QueryLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(inserted.affected_rows, 1);
+
+        let result = connector
+            .execute_with_params(
+                "SELECT body, data FROM notes WHERE id = ?",
+                &[QueryParam::Int(1)],
+                None,
+                None,
+                None,
+                &QueryLimits::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows[0][0], serde_json::json!("'; DROP TABLE notes; --"));
+        assert_eq!(result.rows[0][1], serde_json::json!("raw"));
+    }
+
+    #[tokio::test]
+    async fn test_row_limit_and_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let mut config = create_test_config(&path);
+        config.limits.max_rows = Some(3);
+        let connector = SqliteConnector::connect(&config).await.unwrap();
+        let count_to = |n: u32| format!("WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < {}) SELECT i FROM n", n);
+
+        // The connection default applies...
+        let capped = connector.execute(&count_to(10)).await.unwrap();
+        assert_eq!(capped.rows.len(), 3);
+        assert!(capped.truncated);
+
+        // ...unless the call overrides it
+        let limits = QueryLimits {
+            timeout_ms: None,
+            max_rows: Some(20),
+        };
+        let result = connector
+            .execute_with_params(&count_to(10), &[], None, None, None, &limits)
+            .await
+            .unwrap();
+        assert_eq!(result.rows.len(), 10);
+        assert!(!result.truncated);
+
+        let limits = QueryLimits {
+            timeout_ms: Some(50),
+            max_rows: None,
+        };
+        let slow = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT COUNT(*) FROM n";
+        let result = connector.execute_with_params(slow, &[], None, None, None, &limits).await;
+        assert!(matches!(result, Err(AppError::QueryTimeout(50))));
+
+        // Streamed queries get the same timeout
+        let sink = |_: StreamEvent| Ok(());
+        let result = connector
+            .execute_streaming(slow, None, None, None, &limits, &StreamOptions::default(), &sink)
+            .await;
+        assert!(matches!(result, Err(AppError::QueryTimeout(50))));
+    }
+
+    #[tokio::test]
+    async fn test_attached_databases_are_listed() {
+        let temp_dir = tempdir().unwrap();
+        let path = create_database_file(temp_dir.path(), "app.db");
+        let fixtures = create_database_file(temp_dir.path(), "fixtures.db");
+        let connector = SqliteConnector::connect(&create_test_config(&path)).await.unwrap();
+
+        connector
+            .execute(&format!("ATTACH DATABASE '{}' AS fixtures", fixtures.display()))
+            .await
+            .unwrap();
+        connector.execute("CREATE TABLE fixtures.orders (id INTEGER)").await.unwrap();
+
+        let databases = connector.get_databases().await.unwrap();
+        let names: Vec<_> = databases.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "fixtures"]);
+
+        let tables = connector.get_tables("fixtures", "").await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "orders");
+        assert_eq!(tables[0].schema, "fixtures");
+    }
+}
@@ -0,0 +1,225 @@
+//! Streaming and paginated result sets! 🌊✨
+//!
+//! `execute` collects every row before converting anything, which is fine for
+//! a quick lookup but freezes the app on `SELECT * FROM big_table`. Two
+//! alternatives live here:
+//!
+//! - **Streaming:** rows are pulled with `fetch`, converted as they arrive and
+//!   pushed to the frontend in batches, up to a row cap
+//! - **Pagination:** a cursor is opened per editor session and the frontend
+//!   asks for one page at a time (`fetch_next_page`)
+//!
+//! The connectors own the database-specific parts; this module has the shared
+//! types and the batching loop. 💪
+
+use futures::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{Column, Either, Row, TypeInfo};
+
+use super::connector::{ColumnInfo, TransactionState};
+use crate::error::AppError;
+
+/// Rows per batch pushed to the frontend unless told otherwise
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+/// Streaming stops after this many rows unless told otherwise
+pub const DEFAULT_MAX_ROWS: usize = 100_000;
+/// Rows per page of a cursor unless told otherwise
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// How a streamed query hands out its rows
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOptions {
+    /// Rows per `StreamEvent::Rows` batch
+    pub batch_size: usize,
+    /// Stop reading after this many rows (the summary says `truncated`)
+    pub max_rows: usize,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_rows: DEFAULT_MAX_ROWS,
+        }
+    }
+}
+
+/// Something pushed to the frontend while a query streams! 📨
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Sent once, right before the first batch of rows
+    Columns { columns: Vec<ColumnInfo> },
+    /// The next batch of rows, in order
+    Rows { rows: Vec<Vec<serde_json::Value>> },
+}
+
+/// Where streamed events go (usually a Tauri channel)
+pub type RowSink<'a> = dyn Fn(StreamEvent) -> Result<(), AppError> + Send + Sync + 'a;
+
+/// What's left to say once a streamed query is done
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamSummary {
+    /// Rows pushed to the sink
+    pub row_count: u64,
+    /// Number of rows affected (for INSERT/UPDATE/DELETE)
+    pub affected_rows: u64,
+    /// True if the row cap cut the result short
+    pub truncated: bool,
+    /// How long the query took in milliseconds
+    pub execution_time_ms: u64,
+    /// Transaction state of the editor session after the query ran
+    pub transaction_state: TransactionState,
+}
+
+/// One page read from a session's cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultPage {
+    /// Column metadata (empty if the page has no rows)
+    pub columns: Vec<ColumnInfo>,
+    /// Row data as JSON values
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// False once the cursor is exhausted (it is closed automatically then)
+    pub has_more: bool,
+}
+
+/// Column metadata of a result row
+pub fn columns_of<R: Row>(row: &R) -> Vec<ColumnInfo> {
+    row.columns()
+        .iter()
+        .map(|col| ColumnInfo {
+            name: col.name().to_string(),
+            data_type: col.type_info().name().to_string(),
+            nullable: true,
+        })
+        .collect()
+}
+
+/// Totals of one `forward_rows` run
+pub struct Forwarded {
+    pub row_count: u64,
+    pub rows_affected: u64,
+    pub truncated: bool,
+}
+
+/// Push rows from a `fetch_many` stream to the sink in batches! 🚚
+///
+/// Each row is converted to JSON as soon as it arrives, so memory use stays
+/// at about one batch. Reading stops when the row cap is hit; the rest of
+/// the result is left unread.
+pub async fn forward_rows<R, D, S>(
+    mut stream: S,
+    options: &StreamOptions,
+    to_json: impl Fn(&R) -> Vec<serde_json::Value>,
+    rows_affected: impl Fn(&D) -> u64,
+    sink: &RowSink<'_>,
+) -> Result<Forwarded, AppError>
+where
+    R: Row,
+    S: Stream<Item = Result<Either<D, R>, sqlx::Error>> + Unpin,
+{
+    let batch_size = options.batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut forwarded = Forwarded {
+        row_count: 0,
+        rows_affected: 0,
+        truncated: false,
+    };
+
+    while let Some(item) = stream.try_next().await? {
+        let row = match item {
+            Either::Left(done) => {
+                forwarded.rows_affected += rows_affected(&done);
+                continue;
+            }
+            Either::Right(row) => row,
+        };
+
+        if forwarded.row_count >= options.max_rows as u64 {
+            forwarded.truncated = true;
+            break;
+        }
+        if forwarded.row_count == 0 {
+            sink(StreamEvent::Columns {
+                columns: columns_of(&row),
+            })?;
+        }
+
+        batch.push(to_json(&row));
+        forwarded.row_count += 1;
+        if batch.len() >= batch_size {
+            sink(StreamEvent::Rows {
+                rows: std::mem::replace(&mut batch, Vec::with_capacity(batch_size)),
+            })?;
+        }
+    }
+
+    if !batch.is_empty() {
+        sink(StreamEvent::Rows { rows: batch })?;
+    }
+    Ok(forwarded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Executor;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_forward_rows_batches_and_caps() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let events = Mutex::new(Vec::new());
+        let sink = |event: StreamEvent| {
+            events.lock().unwrap().push(event);
+            Ok(())
+        };
+        let options = StreamOptions {
+            batch_size: 2,
+            max_rows: 5,
+        };
+
+        let query = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 8) SELECT i FROM n";
+        let forwarded = forward_rows(
+            pool.fetch_many(query),
+            &options,
+            |row: &sqlx::sqlite::SqliteRow| vec![serde_json::json!(row.get::<i64, _>(0))],
+            |done: &sqlx::sqlite::SqliteQueryResult| done.rows_affected(),
+            &sink,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(forwarded.row_count, 5);
+        assert!(forwarded.truncated);
+
+        let events = events.into_inner().unwrap();
+        assert!(matches!(&events[0], StreamEvent::Columns { columns } if columns[0].name == "i"));
+        let batch_sizes: Vec<usize> = events[1..]
+            .iter()
+            .map(|event| match event {
+                StreamEvent::Rows { rows } => rows.len(),
+                StreamEvent::Columns { .. } => 0,
+            })
+            .collect();
+        assert_eq!(batch_sizes, vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_forward_rows_stops_when_sink_fails() {
+        let pool = SqlitePoolOptions::new().connect("sqlite::memory:").await.unwrap();
+        let sink = |_: StreamEvent| Err(AppError::Validation("editor went away".to_string()));
+
+        let result = forward_rows(
+            pool.fetch_many("SELECT 1"),
+            &StreamOptions::default(),
+            |_: &sqlx::sqlite::SqliteRow| vec![],
+            |done: &sqlx::sqlite::SqliteQueryResult| done.rows_affected(),
+            &sink,
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
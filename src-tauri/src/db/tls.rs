@@ -0,0 +1,166 @@
+//! TLS settings for database connections! 🔒✨
+//!
+//! sqlx speaks TLS (via rustls) to both MySQL and PostgreSQL, this module
+//! just translates one set of per-connection settings into each driver's
+//! connect options:
+//!
+//! | `TlsMode`     | PostgreSQL `sslmode` | MySQL `ssl-mode`  |
+//! |---------------|----------------------|-------------------|
+//! | `disable`     | `disable`            | `DISABLED`        |
+//! | `prefer`      | `prefer`             | `PREFERRED`       |
+//! | `require`     | `require`            | `REQUIRED`        |
+//! | `verify-ca`   | `verify-ca`          | `VERIFY_CA`       |
+//! | `verify-full` | `verify-full`        | `VERIFY_IDENTITY` |
+//!
+//! SQLite files have no network connection, so they ignore all of this~
+
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::{MySqlConnectOptions, MySqlSslMode};
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+
+use crate::error::AppError;
+
+/// How strict to be about TLS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsMode {
+    /// Plain text only
+    Disable,
+    /// TLS if the server supports it, plain text otherwise (the drivers' default)
+    #[default]
+    Prefer,
+    /// Always TLS, but any certificate is accepted
+    Require,
+    /// Always TLS, with a certificate signed by a trusted CA
+    VerifyCa,
+    /// Like `verify-ca`, and the certificate must also name the host
+    VerifyFull,
+}
+
+impl TlsMode {
+    /// True for the modes that check the server's certificate
+    pub fn verifies(self) -> bool {
+        matches!(self, TlsMode::VerifyCa | TlsMode::VerifyFull)
+    }
+}
+
+/// TLS settings for one connection! 🔐
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// How strict to be (default `prefer`)
+    #[serde(default)]
+    pub mode: TlsMode,
+    /// PEM bundle of CAs to trust instead of the system's (for `verify-ca`/`verify-full`)
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// PEM client certificate, for servers that authenticate clients by certificate
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    /// PEM private key of the client certificate
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+}
+
+impl TlsConfig {
+    /// Check the settings make sense together
+    ///
+    /// # Arguments
+    /// * `tunneled` - True if the connection goes through an SSH tunnel
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if only one of the client certificate
+    /// and key is set, or if `verify-full` is combined with an SSH tunnel
+    /// (the driver would check the certificate against `127.0.0.1`).
+    pub fn validate(&self, tunneled: bool) -> Result<(), AppError> {
+        if self.client_cert_path.is_some() != self.client_key_path.is_some() {
+            return Err(AppError::Validation(
+                "A TLS client certificate needs both the certificate and its key".to_string(),
+            ));
+        }
+        if tunneled && self.mode == TlsMode::VerifyFull {
+            return Err(AppError::Validation(
+                "verify-full can't check the host name through an SSH tunnel - use verify-ca instead".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply the settings to PostgreSQL connect options
+    pub fn apply_pg(&self, mut options: PgConnectOptions) -> PgConnectOptions {
+        options = options.ssl_mode(match self.mode {
+            TlsMode::Disable => PgSslMode::Disable,
+            TlsMode::Prefer => PgSslMode::Prefer,
+            TlsMode::Require => PgSslMode::Require,
+            TlsMode::VerifyCa => PgSslMode::VerifyCa,
+            TlsMode::VerifyFull => PgSslMode::VerifyFull,
+        });
+        if let Some(path) = &self.ca_cert_path {
+            options = options.ssl_root_cert(path);
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert_path, &self.client_key_path) {
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        options
+    }
+
+    /// Apply the settings to MySQL connect options
+    pub fn apply_mysql(&self, mut options: MySqlConnectOptions) -> MySqlConnectOptions {
+        options = options.ssl_mode(match self.mode {
+            TlsMode::Disable => MySqlSslMode::Disabled,
+            TlsMode::Prefer => MySqlSslMode::Preferred,
+            TlsMode::Require => MySqlSslMode::Required,
+            TlsMode::VerifyCa => MySqlSslMode::VerifyCa,
+            TlsMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+        });
+        if let Some(path) = &self.ca_cert_path {
+            options = options.ssl_ca(path);
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert_path, &self.client_key_path) {
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_serializes_like_sslmode() {
+        let config: TlsConfig = serde_json::from_str(r#"{"mode":"verify-full","ca_cert_path":"/etc/ssl/db-ca.pem"}"#).unwrap();
+        assert_eq!(config.mode, TlsMode::VerifyFull);
+        assert_eq!(config.ca_cert_path.as_deref(), Some("/etc/ssl/db-ca.pem"));
+
+        let defaults: TlsConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults, TlsConfig::default());
+        assert_eq!(defaults.mode, TlsMode::Prefer);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = TlsConfig {
+            mode: TlsMode::VerifyFull,
+            client_cert_path: Some("client.pem".to_string()),
+            ..TlsConfig::default()
+        };
+        assert!(matches!(config.validate(false), Err(AppError::Validation(_))));
+
+        config.client_key_path = Some("client.key".to_string());
+        assert!(config.validate(false).is_ok());
+        assert!(matches!(config.validate(true), Err(AppError::Validation(_))));
+
+        config.mode = TlsMode::VerifyCa;
+        assert!(config.validate(true).is_ok());
+    }
+
+    #[test]
+    fn test_apply_pg() {
+        let config = TlsConfig {
+            mode: TlsMode::Require,
+            ..TlsConfig::default()
+        };
+        let options = config.apply_pg(PgConnectOptions::new());
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::Require));
+    }
+}
@@ -5,8 +5,15 @@ mod state;
 mod storage;
 
 use state::AppState;
+use std::time::Duration;
 use tauri::Manager;
 
+/// How often to check whether saved credentials should auto-lock
+const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often to clean up query history past its retention
+const HISTORY_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -32,6 +39,34 @@ pub fn run() {
                     .expect("Failed to initialize storage");
             });
 
+            // Lock saved credentials once they've sat unused for the auto-lock time
+            let lock_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(AUTO_LOCK_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Some(storage) = lock_handle.state::<AppState>().storage.get() {
+                        storage.connections.vault().lock_if_idle();
+                    }
+                }
+            });
+
+            // Trim query history at startup and then every hour
+            let cleanup_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(HISTORY_CLEANUP_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Some(storage) = cleanup_handle.state::<AppState>().storage.get() {
+                        match storage.query_history.cleanup().await {
+                            Ok(0) => {}
+                            Ok(removed) => log::info!("Cleaned up {} query history entries", removed),
+                            Err(e) => log::warn!("Query history cleanup failed: {}", e),
+                        }
+                    }
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -41,21 +76,49 @@ pub fn run() {
             commands::test_connection,
             // Query commands
             commands::execute_query,
+            commands::execute_script,
+            commands::execute_query_stream,
+            commands::open_cursor,
+            commands::fetch_next_page,
+            commands::close_cursor,
+            commands::cancel_query,
+            commands::close_session,
+            commands::begin_transaction,
+            commands::commit_transaction,
+            commands::rollback_transaction,
+            commands::get_transaction_state,
             // Schema commands
             commands::get_databases,
             commands::get_schemas,
             commands::get_tables,
             commands::get_columns,
+            commands::get_indexes,
+            commands::get_constraints,
+            commands::get_object_ddl,
+            commands::list_objects,
+            commands::get_table_stats,
             // Storage commands - Connections
             commands::save_connection,
             commands::update_connection,
             commands::list_connections,
             commands::delete_connection,
             commands::get_connection_config,
+            commands::preview_connection_import,
+            commands::import_connections,
+            commands::export_bundle,
+            commands::import_bundle,
+            // Storage commands - Master password
+            commands::get_master_password_status,
+            commands::enable_master_password,
+            commands::disable_master_password,
+            commands::set_master_password_auto_lock,
+            commands::unlock_credentials,
+            commands::lock_credentials,
             // Storage commands - Workspaces
             commands::list_workspaces,
             commands::create_workspace,
             commands::update_workspace,
+            commands::set_workspace_history_retention,
             commands::delete_workspace,
             commands::add_connection_to_workspace,
             commands::remove_connection_from_workspace,
@@ -63,6 +126,8 @@ pub fn run() {
             // Storage commands - Query History
             commands::add_query_history,
             commands::list_query_history,
+            commands::search_query_history,
+            commands::pin_query_history,
             commands::delete_query_history,
             commands::clear_query_history,
             // Storage commands - Saved Queries
@@ -1,14 +1,19 @@
+use log::warn;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{OnceCell, RwLock};
 use uuid::Uuid;
 
-use crate::db::connector::DatabaseConnector;
+use crate::db::connector::{DatabaseConnector, QueryResult};
 use crate::db::mysql::MySqlConnector;
 use crate::db::postgres::PostgresConnector;
-use crate::db::ConnectionConfig;
-use crate::error::AppError;
-use crate::storage::{ConnectionStorage, QueryHistoryStorage, SavedQueriesStorage, WorkspaceStorage};
+use crate::db::sqlite::SqliteConnector;
+use crate::db::{ConnectionConfig, DatabaseDriver};
+use crate::error::{forget_secrets, remember_secrets, scrub_open_secrets, AppError};
+use crate::storage::{
+    AddQueryHistoryInput, ConnectionStorage, QueryHistoryStorage, SavedQueriesStorage, WorkspaceStorage,
+};
 
 pub struct Storage {
     pub connections: ConnectionStorage,
@@ -19,13 +24,58 @@ pub struct Storage {
 
 pub struct AppState {
     pub connections: RwLock<HashMap<String, Arc<dyn DatabaseConnector>>>,
+    /// Saved connection each open connection records its history under
+    history_targets: RwLock<HashMap<String, HistoryTarget>>,
     pub storage: OnceCell<Storage>,
 }
 
+/// Where an open connection's queries go in history
+#[derive(Clone)]
+struct HistoryTarget {
+    saved_connection_id: String,
+    connection_name: String,
+}
+
+/// One finished statement, as recorded in history
+pub struct QueryRun<'a> {
+    pub query: &'a str,
+    pub execution_time_ms: u64,
+    pub row_count: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl<'a> QueryRun<'a> {
+    /// A statement that ran (script statements may still carry an error)
+    pub fn from_result(query: &'a str, result: &QueryResult) -> Self {
+        let row_count = if result.columns.is_empty() {
+            result.affected_rows
+        } else {
+            result.rows.len() as u64
+        };
+        Self {
+            query,
+            execution_time_ms: result.execution_time_ms,
+            row_count: Some(row_count),
+            error: result.error.clone(),
+        }
+    }
+
+    /// A statement that failed, timed from `started`
+    pub fn failed(query: &'a str, started: Instant, error: &AppError) -> Self {
+        Self {
+            query,
+            execution_time_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            error: Some(scrub_open_secrets(&error.to_string())),
+        }
+    }
+}
+
 impl AppState {
     pub fn new() -> Self {
         Self {
             connections: RwLock::new(HashMap::new()),
+            history_targets: RwLock::new(HashMap::new()),
             storage: OnceCell::new(),
         }
     }
@@ -34,11 +84,8 @@ impl AppState {
         let conn_storage = ConnectionStorage::new(app_data_dir).await?;
         let pool = conn_storage.get_pool();
         let workspace_storage = WorkspaceStorage::new(pool.clone());
-        workspace_storage.initialize_schema().await?;
         let query_history_storage = QueryHistoryStorage::new(pool.clone());
-        query_history_storage.initialize_schema().await?;
         let saved_queries_storage = SavedQueriesStorage::new(pool);
-        saved_queries_storage.initialize_schema().await?;
         let storage = Storage {
             connections: conn_storage,
             workspaces: workspace_storage,
@@ -49,25 +96,116 @@ impl AppState {
         Ok(())
     }
 
-    pub async fn connect(&self, config: &ConnectionConfig) -> Result<String, AppError> {
+    /// Open a connection and return its ID.
+    ///
+    /// Queries run on it are recorded in history under `saved_connection_id`,
+    /// unless that saved connection turns history off. Connections opened
+    /// without a saved connection aren't recorded.
+    pub async fn connect(
+        &self,
+        config: &ConnectionConfig,
+        saved_connection_id: Option<&str>,
+    ) -> Result<String, AppError> {
+        // The stored opt-out decides, not whatever the frontend put in the config
+        let history_target = match (saved_connection_id, self.storage.get()) {
+            (Some(saved_connection_id), Some(storage)) => storage
+                .connections
+                .get(saved_connection_id)
+                .await?
+                .filter(|saved| saved.record_history)
+                .map(|saved| HistoryTarget {
+                    saved_connection_id: saved.id,
+                    connection_name: saved.name,
+                }),
+            _ => None,
+        };
+
         let connector: Arc<dyn DatabaseConnector> = match config.driver {
             crate::db::DatabaseDriver::MySQL => Arc::new(MySqlConnector::connect(config).await?),
             crate::db::DatabaseDriver::PostgreSQL => Arc::new(PostgresConnector::connect(config).await?),
+            crate::db::DatabaseDriver::SQLite => Arc::new(SqliteConnector::connect(config).await?),
         };
         let connection_id = Uuid::new_v4().to_string();
+        remember_secrets(&connection_id, &config.secrets());
+        if let Some(history_target) = history_target {
+            self.history_targets.write().await.insert(connection_id.clone(), history_target);
+        }
         let mut connections = self.connections.write().await;
         connections.insert(connection_id.clone(), connector);
         Ok(connection_id)
     }
 
-    pub async fn disconnect(&self, connection_id: &str) -> Result<(), AppError> {
+    /// Close a connection, guarding sessions with open transactions.
+    ///
+    /// Without `force`, a connection whose sessions still have transactions
+    /// open is left alone and `AppError::OpenTransactions` is returned. With
+    /// `force`, those transactions are rolled back by closing the connection.
+    pub async fn disconnect(&self, connection_id: &str, force: bool) -> Result<(), AppError> {
         let mut connections = self.connections.write().await;
+        if let Some(connector) = connections.get(connection_id) {
+            let open = connector.open_transactions();
+            if !open.is_empty() {
+                if !force {
+                    return Err(AppError::OpenTransactions(open.len()));
+                }
+                warn!(
+                    "Disconnecting '{}' rolls back open transactions in sessions: {}",
+                    connection_id,
+                    open.join(", ")
+                );
+            }
+        }
+        self.history_targets.write().await.remove(connection_id);
+        forget_secrets(connection_id);
         if let Some(connector) = connections.remove(connection_id) {
             connector.close().await?;
         }
         Ok(())
     }
 
+    /// Record finished statements in history, if the connection records it.
+    ///
+    /// `database` and `context` are what the statements ran with. Failing
+    /// to write history never fails the query - it's only logged.
+    pub async fn record_history(
+        &self,
+        connection_id: &str,
+        connector: &dyn DatabaseConnector,
+        database: Option<&str>,
+        context: Option<&str>,
+        runs: &[QueryRun<'_>],
+    ) {
+        let Some(target) = self.history_targets.read().await.get(connection_id).cloned() else {
+            return;
+        };
+        let Some(storage) = self.storage.get() else {
+            return;
+        };
+
+        // MySQL's context is the database; PostgreSQL's is the schema
+        let (database_name, schema_name) = match connector.driver() {
+            DatabaseDriver::MySQL => (context.or(database), None),
+            _ => (database, context),
+        };
+
+        for run in runs {
+            let input = AddQueryHistoryInput {
+                query: run.query.trim().to_string(),
+                connection_id: target.saved_connection_id.clone(),
+                connection_name: target.connection_name.clone(),
+                database_name: database_name.map(str::to_string),
+                schema_name: schema_name.map(str::to_string),
+                execution_time_ms: Some(run.execution_time_ms as i64),
+                row_count: run.row_count.map(|n| n as i64),
+                success: run.error.is_none(),
+                error_message: run.error.clone(),
+            };
+            if let Err(e) = storage.query_history.add(&input).await {
+                warn!("Couldn't record a query of '{}' in history: {}", target.connection_name, e);
+            }
+        }
+    }
+
     pub async fn get_connection(&self, connection_id: &str) -> Result<Arc<dyn DatabaseConnector>, AppError> {
         let connections = self.connections.read().await;
         connections.get(connection_id).cloned().ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))
@@ -79,3 +217,81 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connector::QueryLimits;
+    use crate::db::tls::TlsConfig;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn sqlite_config(file_path: &std::path::Path, record_history: bool) -> ConnectionConfig {
+        ConnectionConfig {
+            name: "Fixtures".to_string(),
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database: None,
+            driver: DatabaseDriver::SQLite,
+            file_path: Some(file_path.display().to_string()),
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_history_follows_connection_settings() {
+        let temp_dir = tempdir().unwrap();
+        let state = AppState::new();
+        state.initialize_storage(temp_dir.path()).await.unwrap();
+        let db_path = temp_dir.path().join("fixtures.db");
+        std::fs::File::create(&db_path).unwrap();
+
+        let connections = &state.storage.get().unwrap().connections;
+        let saved = connections.save(&sqlite_config(&db_path, true)).await.unwrap();
+        let secret = connections.save(&sqlite_config(&db_path, false)).await.unwrap();
+
+        let recorded = state.connect(&sqlite_config(&db_path, true), Some(&saved.id)).await.unwrap();
+        // The frontend's config can't turn history back on for an opted-out connection
+        let opted_out = state.connect(&sqlite_config(&db_path, true), Some(&secret.id)).await.unwrap();
+        let unsaved = state.connect(&sqlite_config(&db_path, true), None).await.unwrap();
+        let unknown = state.connect(&sqlite_config(&db_path, true), Some("deleted")).await.unwrap();
+
+        for connection_id in [&recorded, &opted_out, &unsaved, &unknown] {
+            let connector = state.get_connection(connection_id).await.unwrap();
+            let started = Instant::now();
+            let ok = connector.execute_with_context("SELECT 1", None, None, None).await.unwrap();
+            let err = connector
+                .execute_with_context("SELECT * FROM missing", None, None, None)
+                .await
+                .unwrap_err();
+            let runs = [
+                QueryRun::from_result("SELECT 1", &ok),
+                QueryRun::failed("SELECT * FROM missing", started, &err),
+            ];
+            state
+                .record_history(connection_id, connector.as_ref(), Some("main"), None, &runs)
+                .await;
+        }
+
+        let history = state.storage.get().unwrap().query_history.list(None, None).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|e| e.connection_id == saved.id && e.connection_name == "Fixtures"));
+        let ok = history.iter().find(|e| e.success).unwrap();
+        assert_eq!(ok.query, "SELECT 1");
+        assert_eq!(ok.row_count, Some(1));
+        assert_eq!(ok.database_name.as_deref(), Some("main"));
+        let failed = history.iter().find(|e| !e.success).unwrap();
+        assert!(failed.error_message.as_deref().unwrap().contains("missing"));
+
+        // Disconnecting forgets where to record
+        state.disconnect(&recorded, false).await.unwrap();
+        assert!(state.history_targets.read().await.is_empty());
+    }
+}
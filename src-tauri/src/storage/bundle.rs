@@ -0,0 +1,552 @@
+//! Move your whole setup to another machine! 🧳✨
+//!
+//! Saved passwords are encrypted with a key derived from the machine ID, so a
+//! copied `connections.db` can't be read anywhere else. An export bundle
+//! instead holds connections (with their passwords), workspaces and saved
+//! queries in one file, encrypted with a key derived from a passphrase the
+//! user picks.
+//!
+//! The file is JSON with a random salt and the encrypted payload:
+//!
+//! ```json
+//! { "format": "anko-export", "version": 1, "salt": "...", "payload": "<hex>" }
+//! ```
+//!
+//! Importing merges into the existing storage. Connections that match a saved
+//! one (same host, port, user and database), workspaces with the same name and
+//! saved queries with the same name in the same workspace are conflicts,
+//! handled as the user chooses~ 🌟
+//!
+//! Passwords may live in the OS keychain, which can't share a transaction
+//! with the database, so an import isn't all-or-nothing. Instead every item
+//! that fails is listed in the summary and the rest still go in.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::connector::ConnectionConfig;
+use crate::error::AppError;
+use crate::storage::encryption::Encryptor;
+use crate::storage::import::find_duplicate;
+use crate::storage::{
+    ConnectionStorage, CreateSavedQueryInput, SavedQueriesStorage, SavedQuery, UpdateSavedQueryInput, Workspace,
+    WorkspaceConfig, WorkspaceStorage,
+};
+
+/// Marks a file as an Anko export
+const BUNDLE_FORMAT: &str = "anko-export";
+/// Version of the bundle layout, bumped on incompatible changes
+const BUNDLE_VERSION: u32 = 1;
+/// Shortest passphrase accepted for an export
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// What to do with an imported item that already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Keep the existing item as it is
+    Skip,
+    /// Overwrite the existing item with the imported one
+    Replace,
+    /// Add the imported item next to the existing one
+    KeepBoth,
+}
+
+/// What an import did, for showing to the user 📋
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleImportSummary {
+    pub connections_created: usize,
+    pub connections_replaced: usize,
+    pub connections_skipped: usize,
+    pub workspaces_created: usize,
+    /// Imported workspaces whose connections went into an existing workspace of the same name
+    pub workspaces_merged: usize,
+    pub saved_queries_created: usize,
+    pub saved_queries_replaced: usize,
+    pub saved_queries_skipped: usize,
+    /// Items that couldn't be saved; everything else was imported
+    pub failed: Vec<BundleImportFailure>,
+}
+
+/// Kind of item in a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleItemKind {
+    Connection,
+    Workspace,
+    SavedQuery,
+}
+
+/// An item an import couldn't save, and why 💥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleImportFailure {
+    pub kind: BundleItemKind,
+    pub name: String,
+    pub error: String,
+}
+
+impl BundleImportSummary {
+    fn fail(&mut self, kind: BundleItemKind, name: &str, error: AppError) {
+        self.failed.push(BundleImportFailure {
+            kind,
+            name: name.to_string(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// The file as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFile {
+    format: String,
+    version: u32,
+    /// B64 salt for the passphrase key
+    salt: String,
+    /// Hex of the encrypted `BundlePayload` JSON
+    payload: String,
+}
+
+/// Everything in a bundle, before encryption
+#[derive(Debug, Serialize, Deserialize)]
+struct BundlePayload {
+    connections: Vec<BundleConnection>,
+    workspaces: Vec<Workspace>,
+    saved_queries: Vec<SavedQuery>,
+}
+
+/// A connection with its plaintext secrets and its id on the exporting machine
+///
+/// The id is only used to re-link workspaces and saved queries on import.
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleConnection {
+    id: String,
+    #[serde(flatten)]
+    config: ConnectionConfig,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+/// Export every connection, workspace and saved query, encrypted with `passphrase` 📦
+///
+/// Returns the contents of the export file.
+///
+/// # Errors
+/// Returns `AppError::Validation` if the passphrase is too short, or any
+/// storage/encryption error while reading the saved data.
+pub async fn export(
+    connections: &ConnectionStorage,
+    workspaces: &WorkspaceStorage,
+    saved_queries: &SavedQueriesStorage,
+    passphrase: &str,
+) -> Result<String, AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "The export passphrase needs at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    let mut bundle_connections = Vec::new();
+    for saved in connections.list().await? {
+        let (password, ssh_secret) = connections.load_secrets(&saved).await?;
+        bundle_connections.push(BundleConnection {
+            id: saved.id.clone(),
+            config: saved.to_config(password, ssh_secret),
+        });
+    }
+
+    let payload = BundlePayload {
+        connections: bundle_connections,
+        workspaces: workspaces.list().await?,
+        saved_queries: saved_queries.list(None).await?,
+    };
+    let payload = serde_json::to_string(&payload)
+        .map_err(|e| AppError::Storage(format!("Failed to serialize export: {}", e)))?;
+
+    let salt = Encryptor::generate_salt()?;
+    let encrypted = Encryptor::with_passphrase(passphrase, &salt)?.encrypt(&payload)?;
+
+    let file = BundleFile {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        salt,
+        payload: to_hex(&encrypted),
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| AppError::Storage(format!("Failed to serialize export: {}", e)))
+}
+
+/// Decrypt an export file's contents
+fn open_bundle(contents: &str, passphrase: &str) -> Result<BundlePayload, AppError> {
+    let file: BundleFile = serde_json::from_str(contents)
+        .map_err(|_| AppError::Validation("This is not an Anko export file".to_string()))?;
+    if file.format != BUNDLE_FORMAT {
+        return Err(AppError::Validation("This is not an Anko export file".to_string()));
+    }
+    if file.version > BUNDLE_VERSION {
+        return Err(AppError::Validation(format!(
+            "This export was made by a newer version of Anko (format version {})",
+            file.version
+        )));
+    }
+
+    let encrypted =
+        from_hex(&file.payload).ok_or_else(|| AppError::Validation("The export file is damaged".to_string()))?;
+    let payload = Encryptor::with_passphrase(passphrase, &file.salt)?
+        .decrypt(&encrypted)
+        .map_err(|_| AppError::Validation("Wrong passphrase, or the export file is damaged".to_string()))?;
+    serde_json::from_str(&payload).map_err(|e| AppError::Validation(format!("The export file is damaged: {}", e)))
+}
+
+/// Import an export file, merging it into the existing storage 📥
+///
+/// Connections are imported first so workspaces and saved queries can be
+/// pointed at their new ids. Skipped connections map to the existing one,
+/// so workspace membership still carries over.
+///
+/// An item that fails to save doesn't stop the import - it's listed in
+/// `BundleImportSummary::failed`. A failed connection is left out of its
+/// workspaces and saved queries, and saved queries of a failed workspace
+/// are left without one.
+///
+/// # Errors
+/// Returns `AppError::Validation` for a wrong passphrase or a file that isn't
+/// a readable export, or a storage error while reading what's already saved.
+pub async fn import(
+    connections: &ConnectionStorage,
+    workspaces: &WorkspaceStorage,
+    saved_queries: &SavedQueriesStorage,
+    contents: &str,
+    passphrase: &str,
+    on_conflict: ConflictResolution,
+) -> Result<BundleImportSummary, AppError> {
+    let payload = open_bundle(contents, passphrase)?;
+    let mut summary = BundleImportSummary::default();
+
+    // Exported connection id -> id on this machine
+    let mut connection_ids: HashMap<String, String> = HashMap::new();
+    let existing_connections = connections.list().await?;
+    for connection in &payload.connections {
+        let duplicate = find_duplicate(&connection.config, &existing_connections);
+        let id = match (duplicate, on_conflict) {
+            (Some(existing), ConflictResolution::Skip) => {
+                summary.connections_skipped += 1;
+                existing.id.clone()
+            }
+            (Some(existing), ConflictResolution::Replace) => {
+                match connections.update(&existing.id, &connection.config).await {
+                    Ok(()) => summary.connections_replaced += 1,
+                    Err(e) => {
+                        summary.fail(BundleItemKind::Connection, &connection.config.name, e);
+                        continue;
+                    }
+                }
+                existing.id.clone()
+            }
+            (None, _) | (Some(_), ConflictResolution::KeepBoth) => match connections.save(&connection.config).await {
+                Ok(saved) => {
+                    summary.connections_created += 1;
+                    saved.id
+                }
+                Err(e) => {
+                    summary.fail(BundleItemKind::Connection, &connection.config.name, e);
+                    continue;
+                }
+            },
+        };
+        connection_ids.insert(connection.id.clone(), id);
+    }
+
+    // Exported workspace id -> id on this machine
+    let mut workspace_ids: HashMap<String, String> = HashMap::new();
+    let existing_workspaces = workspaces.list().await?;
+    for workspace in &payload.workspaces {
+        let same = existing_workspaces
+            .iter()
+            .find(|existing| (workspace.is_default && existing.is_default) || existing.name == workspace.name);
+        let config = WorkspaceConfig {
+            name: workspace.name.clone(),
+            icon: workspace.icon.clone(),
+        };
+        // Resolves to the workspace's id here and whether it's a new one
+        let saved = async {
+            let (id, created) = match (same, on_conflict) {
+                (Some(existing), _) if workspace.is_default => (existing.id.clone(), false),
+                (Some(existing), ConflictResolution::Skip) => (existing.id.clone(), false),
+                (Some(existing), ConflictResolution::Replace) => {
+                    workspaces.update(&existing.id, &config).await?;
+                    let id = workspaces.set_history_retention(&existing.id, &workspace.history_retention).await?.id;
+                    (id, false)
+                }
+                (None, _) | (Some(_), ConflictResolution::KeepBoth) => {
+                    let id = workspaces.create(&config).await?.id;
+                    let id = workspaces.set_history_retention(&id, &workspace.history_retention).await?.id;
+                    (id, true)
+                }
+            };
+
+            for connection_id in &workspace.connection_ids {
+                if let Some(connection_id) = connection_ids.get(connection_id) {
+                    workspaces.add_connection(&id, connection_id).await?;
+                }
+            }
+            Ok::<_, AppError>((id, created))
+        }
+        .await;
+
+        match saved {
+            Ok((id, created)) => {
+                if created {
+                    summary.workspaces_created += 1;
+                } else {
+                    summary.workspaces_merged += 1;
+                }
+                workspace_ids.insert(workspace.id.clone(), id);
+            }
+            Err(e) => summary.fail(BundleItemKind::Workspace, &workspace.name, e),
+        }
+    }
+
+    let existing_queries = saved_queries.list(None).await?;
+    for query in &payload.saved_queries {
+        let workspace_id = query.workspace_id.as_ref().and_then(|id| workspace_ids.get(id)).cloned();
+        let connection_id = query.connection_id.as_ref().and_then(|id| connection_ids.get(id)).cloned();
+        let same = existing_queries
+            .iter()
+            .find(|existing| existing.name == query.name && existing.workspace_id == workspace_id);
+
+        match (same, on_conflict) {
+            (Some(_), ConflictResolution::Skip) => summary.saved_queries_skipped += 1,
+            (Some(existing), ConflictResolution::Replace) => {
+                let input = UpdateSavedQueryInput {
+                    name: Some(query.name.clone()),
+                    query: Some(query.query.clone()),
+                    description: query.description.clone(),
+                    workspace_id,
+                    connection_id,
+                    database_name: query.database_name.clone(),
+                };
+                match saved_queries.update(&existing.id, &input).await {
+                    Ok(_) => summary.saved_queries_replaced += 1,
+                    Err(e) => summary.fail(BundleItemKind::SavedQuery, &query.name, e),
+                }
+            }
+            (None, _) | (Some(_), ConflictResolution::KeepBoth) => {
+                let input = CreateSavedQueryInput {
+                    name: query.name.clone(),
+                    query: query.query.clone(),
+                    description: query.description.clone(),
+                    workspace_id,
+                    connection_id,
+                    database_name: query.database_name.clone(),
+                };
+                match saved_queries.create(&input).await {
+                    Ok(_) => summary.saved_queries_created += 1,
+                    Err(e) => summary.fail(BundleItemKind::SavedQuery, &query.name, e),
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connector::{DatabaseDriver, QueryLimits};
+    use crate::db::tls::TlsConfig;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    async fn open_storage(dir: &std::path::Path) -> (ConnectionStorage, WorkspaceStorage, SavedQueriesStorage) {
+        let connections = ConnectionStorage::new(dir).await.unwrap();
+        let workspaces = WorkspaceStorage::new(connections.get_pool());
+        let saved_queries = SavedQueriesStorage::new(connections.get_pool());
+        (connections, workspaces, saved_queries)
+    }
+
+    fn shop_config() -> ConnectionConfig {
+        ConnectionConfig {
+            name: "Shop".to_string(),
+            host: "db.internal".to_string(),
+            port: 5432,
+            username: "app".to_string(),
+            password: "s3cret".to_string(),
+            database: Some("shop".to_string()),
+            driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source_dir = tempdir().unwrap();
+        let (connections, workspaces, saved_queries) = open_storage(source_dir.path()).await;
+        let saved = connections.save(&shop_config()).await.unwrap();
+        let team = workspaces
+            .create(&WorkspaceConfig {
+                name: "Team".to_string(),
+                icon: "users".to_string(),
+            })
+            .await
+            .unwrap();
+        workspaces.add_connection(&team.id, &saved.id).await.unwrap();
+        saved_queries
+            .create(&CreateSavedQueryInput {
+                name: "Open orders".to_string(),
+                query: "SELECT * FROM orders WHERE status = 'open'".to_string(),
+                description: None,
+                workspace_id: Some(team.id.clone()),
+                connection_id: Some(saved.id.clone()),
+                database_name: Some("shop".to_string()),
+            })
+            .await
+            .unwrap();
+
+        let exported = export(&connections, &workspaces, &saved_queries, "long enough passphrase")
+            .await
+            .unwrap();
+        assert!(!exported.contains("s3cret"));
+
+        let target_dir = tempdir().unwrap();
+        let (connections, workspaces, saved_queries) = open_storage(target_dir.path()).await;
+        let summary = import(
+            &connections,
+            &workspaces,
+            &saved_queries,
+            &exported,
+            "long enough passphrase",
+            ConflictResolution::Skip,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.connections_created, 1);
+        assert_eq!(summary.workspaces_created, 1);
+        assert_eq!(summary.workspaces_merged, 1); // the default workspace
+        assert_eq!(summary.saved_queries_created, 1);
+
+        let imported = connections.list().await.unwrap().remove(0);
+        assert_eq!(connections.decrypt_password(&imported.encrypted_password).unwrap(), "s3cret");
+        let team = workspaces
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|w| w.name == "Team")
+            .unwrap();
+        assert_eq!(team.connection_ids, vec![imported.id.clone()]);
+        let query = saved_queries.list(None).await.unwrap().remove(0);
+        assert_eq!(query.workspace_id, Some(team.id));
+        assert_eq!(query.connection_id, Some(imported.id));
+
+        // Importing again only finds conflicts
+        let again = import(
+            &connections,
+            &workspaces,
+            &saved_queries,
+            &exported,
+            "long enough passphrase",
+            ConflictResolution::Skip,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.connections_skipped, 1);
+        assert_eq!(again.saved_queries_skipped, 1);
+        assert_eq!(connections.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_failed_items() {
+        let source_dir = tempdir().unwrap();
+        let (connections, workspaces, saved_queries) = open_storage(source_dir.path()).await;
+        connections.save(&shop_config()).await.unwrap();
+        for name in ["Broken", "Fine"] {
+            saved_queries
+                .create(&CreateSavedQueryInput {
+                    name: name.to_string(),
+                    query: "SELECT 1".to_string(),
+                    description: None,
+                    workspace_id: None,
+                    connection_id: None,
+                    database_name: None,
+                })
+                .await
+                .unwrap();
+        }
+        let exported = export(&connections, &workspaces, &saved_queries, "long enough passphrase")
+            .await
+            .unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let (connections, workspaces, saved_queries) = open_storage(target_dir.path()).await;
+        sqlx::query(
+            "CREATE TRIGGER no_broken BEFORE INSERT ON saved_queries WHEN NEW.name = 'Broken' \
+             BEGIN SELECT RAISE(ABORT, 'refused'); END",
+        )
+        .execute(&connections.get_pool())
+        .await
+        .unwrap();
+
+        let summary = import(
+            &connections,
+            &workspaces,
+            &saved_queries,
+            &exported,
+            "long enough passphrase",
+            ConflictResolution::Skip,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.connections_created, 1);
+        assert_eq!(summary.saved_queries_created, 1);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].kind, BundleItemKind::SavedQuery);
+        assert_eq!(summary.failed[0].name, "Broken");
+        assert!(summary.failed[0].error.contains("refused"));
+        assert_eq!(saved_queries.list(None).await.unwrap()[0].name, "Fine");
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_wrong_passphrase() {
+        let temp_dir = tempdir().unwrap();
+        let (connections, workspaces, saved_queries) = open_storage(temp_dir.path()).await;
+        connections.save(&shop_config()).await.unwrap();
+
+        assert!(matches!(
+            export(&connections, &workspaces, &saved_queries, "short").await,
+            Err(AppError::Validation(_))
+        ));
+
+        let exported = export(&connections, &workspaces, &saved_queries, "long enough passphrase")
+            .await
+            .unwrap();
+        let result = import(
+            &connections,
+            &workspaces,
+            &saved_queries,
+            &exported,
+            "not the passphrase",
+            ConflictResolution::Replace,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
@@ -6,14 +6,29 @@
 //!
 //! All passwords are encrypted using the Encryptor module before storage,
 //! so they're safe even if someone gets access to the database file~ 🔐💪
+//! The same goes for SSH tunnel passwords and key passphrases, which are kept
+//! apart from the rest of the tunnel settings. Connections can also keep their
+//! secrets in the OS keyring, or not store them at all (see `credentials`).
 
+use log::warn;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use sqlx::{
+    sqlite::{SqlitePoolOptions, SqliteRow},
+    Pool, Row, Sqlite,
+};
 use uuid::Uuid;
 
-use crate::db::connector::{ConnectionConfig, DatabaseDriver};
+use crate::db::connector::{ConnectionConfig, DatabaseDriver, QueryLimits};
+use crate::db::tls::TlsConfig;
+use crate::db::tunnel::SshTunnelConfig;
 use crate::error::AppError;
-use crate::storage::encryption::Encryptor;
+use crate::storage::credentials::{
+    CredentialBackend, CredentialStore, EncryptedStore, PromptStore, SecretKind, SecretServiceStore,
+};
+use crate::storage::migrations;
+use crate::storage::vault::CredentialVault;
 
 /// A saved database connection with encrypted password! 🌸💾
 ///
@@ -34,11 +49,36 @@ pub struct SavedConnection {
     pub username: String,
     /// Optional default database name
     pub database: Option<String>,
-    /// Database type (MySQL or PostgreSQL)
+    /// Database type (MySQL, PostgreSQL or SQLite)
     pub driver: DatabaseDriver,
+    /// Path to the database file (SQLite only)
+    pub file_path: Option<String>,
+    /// Default query timeout and row limit
+    #[serde(default)]
+    pub limits: QueryLimits,
+    /// SSH tunnel settings, without the password or passphrase
+    #[serde(default)]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    /// TLS mode and certificate paths
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Extra driver parameters
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// Where the password and SSH secret are kept
+    #[serde(default)]
+    pub credential_backend: CredentialBackend,
+    /// Whether queries on this connection are recorded in history
+    #[serde(default = "default_record_history")]
+    pub record_history: bool,
     /// Encrypted password (never serialized to frontend!)
+    ///
+    /// Empty unless the credential backend is `Encrypted`.
     #[serde(skip_serializing)]
     pub encrypted_password: Vec<u8>,
+    /// Encrypted SSH password or key passphrase (never serialized either!)
+    #[serde(default, skip_serializing)]
+    pub encrypted_ssh_secret: Option<Vec<u8>>,
 }
 
 impl SavedConnection {
@@ -49,10 +89,11 @@ impl SavedConnection {
     ///
     /// # Arguments
     /// * `password` - The decrypted plaintext password
+    /// * `ssh_secret` - The decrypted SSH password or key passphrase, if any
     ///
     /// # Returns
     /// A ConnectionConfig ready to create a database connection! 🚀
-    pub fn to_config(&self, password: String) -> ConnectionConfig {
+    pub fn to_config(&self, password: String, ssh_secret: Option<String>) -> ConnectionConfig {
         ConnectionConfig {
             name: self.name.clone(),
             host: self.host.clone(),
@@ -61,10 +102,103 @@ impl SavedConnection {
             password,
             database: self.database.clone(),
             driver: self.driver,
+            file_path: self.file_path.clone(),
+            limits: self.limits,
+            ssh_tunnel: self.ssh_tunnel.clone().map(|tunnel| tunnel.with_secret(ssh_secret)),
+            tls: self.tls.clone(),
+            params: self.params.clone(),
+            credential_backend: self.credential_backend,
+            record_history: self.record_history,
         }
     }
 }
 
+fn default_record_history() -> bool {
+    true
+}
+
+/// Storage representation of a driver (the `driver` column)
+fn driver_to_str(driver: DatabaseDriver) -> &'static str {
+    match driver {
+        DatabaseDriver::MySQL => "mysql",
+        DatabaseDriver::PostgreSQL => "postgresql",
+        DatabaseDriver::SQLite => "sqlite",
+    }
+}
+
+/// Parse the `driver` column, falling back to MySQL for unknown values
+fn driver_from_str(driver: &str) -> DatabaseDriver {
+    match driver {
+        "postgresql" => DatabaseDriver::PostgreSQL,
+        "sqlite" => DatabaseDriver::SQLite,
+        _ => DatabaseDriver::MySQL,
+    }
+}
+
+/// Map a `connections` row (selected with `SELECT_COLUMNS`) to a SavedConnection
+fn row_to_saved_connection(row: &SqliteRow) -> SavedConnection {
+    let driver_str: String = row.get(7);
+
+    SavedConnection {
+        id: row.get(0),
+        name: row.get(1),
+        host: row.get(2),
+        port: row.get::<i32, _>(3) as u16,
+        username: row.get(4),
+        encrypted_password: row.get(5),
+        database: row.get(6),
+        driver: driver_from_str(&driver_str),
+        file_path: row.get(8),
+        limits: QueryLimits {
+            timeout_ms: row.get::<Option<i64>, _>(9).map(|v| v as u64),
+            max_rows: row.get::<Option<i64>, _>(10).map(|v| v as u64),
+        },
+        ssh_tunnel: row
+            .get::<Option<String>, _>(11)
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        encrypted_ssh_secret: row.get(12),
+        tls: row
+            .get::<Option<String>, _>(13)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        params: row
+            .get::<Option<String>, _>(14)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        credential_backend: CredentialBackend::from_column(row.get::<Option<String>, _>(15).as_deref()),
+        record_history: row.get::<i32, _>(16) != 0,
+    }
+}
+
+/// The `ssh_tunnel` column: tunnel settings as JSON, minus the secret
+fn ssh_tunnel_to_json(config: &ConnectionConfig) -> Result<Option<String>, AppError> {
+    config
+        .ssh_tunnel
+        .as_ref()
+        .map(|tunnel| {
+            serde_json::to_string(&tunnel.without_secret())
+                .map_err(|e| AppError::Validation(format!("Failed to store SSH tunnel settings: {}", e)))
+        })
+        .transpose()
+}
+
+/// The `tls` column: TLS settings as JSON
+fn tls_to_json(config: &ConnectionConfig) -> Result<String, AppError> {
+    serde_json::to_string(&config.tls)
+        .map_err(|e| AppError::Validation(format!("Failed to store TLS settings: {}", e)))
+}
+
+/// The `driver_params` column: extra driver parameters as JSON
+fn params_to_json(config: &ConnectionConfig) -> Result<String, AppError> {
+    serde_json::to_string(&config.params)
+        .map_err(|e| AppError::Validation(format!("Failed to store connection parameters: {}", e)))
+}
+
+/// Columns read by `row_to_saved_connection`, in order
+const SELECT_COLUMNS: &str =
+    "id, name, host, port, username, encrypted_password, database, driver, file_path, query_timeout_ms, max_rows, \
+     ssh_tunnel, encrypted_ssh_secret, tls, driver_params, credential_backend, record_history";
+
 /// SQLite storage for saved connections with encryption! 💾🔐
 ///
 /// Manages persistent storage of database connections in a local SQLite file.
@@ -73,15 +207,19 @@ impl SavedConnection {
 pub struct ConnectionStorage {
     /// SQLite connection pool
     pool: Pool<Sqlite>,
-    /// Password encryptor (handles AES-256-GCM encryption)
-    encryptor: Encryptor,
+    /// Hands out the password encryptor, locked while a master password isn't entered
+    vault: Arc<CredentialVault>,
+    /// Secrets encrypted into the connection rows
+    encrypted: EncryptedStore,
+    /// The OS keyring (swapped for a mock in tests)
+    keyring: Box<dyn CredentialStore>,
 }
 
 impl ConnectionStorage {
     /// Create new ConnectionStorage and initialize the database schema! 🌟
     ///
-    /// Creates the SQLite database file if it doesn't exist and sets up
-    /// the connections table. The database is stored in your app's data
+    /// Creates the SQLite database file if it doesn't exist and migrates
+    /// it to the latest schema (see `migrations`). The database is stored in your app's data
     /// directory for persistence across restarts~ 💪
     ///
     /// # Arguments
@@ -93,6 +231,14 @@ impl ConnectionStorage {
     /// # Errors
     /// Returns `AppError` if database creation or schema initialization fails
     pub async fn new(app_data_dir: &std::path::Path) -> Result<Self, AppError> {
+        Self::with_keyring(app_data_dir, Box::new(SecretServiceStore)).await
+    }
+
+    /// Like `new`, but keeping keyring-backed secrets in the given store.
+    pub async fn with_keyring(
+        app_data_dir: &std::path::Path,
+        keyring: Box<dyn CredentialStore>,
+    ) -> Result<Self, AppError> {
         std::fs::create_dir_all(app_data_dir)?;
 
         let db_path = app_data_dir.join("connections.db");
@@ -103,55 +249,77 @@ impl ConnectionStorage {
             .connect(&connection_string)
             .await?;
 
-        let storage = Self {
-            pool,
-            encryptor: Encryptor::new()?,
-        };
+        migrations::run(&pool, &db_path).await?;
+        let vault = Arc::new(CredentialVault::open(pool.clone()).await?);
 
-        storage.initialize_schema().await?;
-
-        Ok(storage)
+        Ok(Self {
+            pool,
+            encrypted: EncryptedStore::new(vault.clone()),
+            vault,
+            keyring,
+        })
     }
 
     pub fn get_pool(&self) -> Pool<Sqlite> {
         self.pool.clone()
     }
 
-    async fn initialize_schema(&self) -> Result<(), AppError> {
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS connections (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                host TEXT NOT NULL,
-                port INTEGER NOT NULL,
-                username TEXT NOT NULL,
-                encrypted_password BLOB NOT NULL,
-                database TEXT,
-                driver TEXT NOT NULL DEFAULT 'mysql',
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Master password settings and lock state
+    pub fn vault(&self) -> &CredentialVault {
+        &self.vault
+    }
 
-        Ok(())
+    /// The store behind a credential backend
+    fn store_for(&self, backend: CredentialBackend) -> &dyn CredentialStore {
+        match backend {
+            CredentialBackend::Encrypted => &self.encrypted,
+            CredentialBackend::Keyring => self.keyring.as_ref(),
+            CredentialBackend::Prompt => &PromptStore,
+        }
+    }
+
+    /// Hand the password and SSH secret to the config's backend.
+    ///
+    /// Returns the `encrypted_password` and `encrypted_ssh_secret` column
+    /// values (empty/`None` for backends that keep secrets elsewhere).
+    async fn store_secrets(&self, id: &str, config: &ConnectionConfig) -> Result<(Vec<u8>, Option<Vec<u8>>), AppError> {
+        let store = self.store_for(config.credential_backend);
+        let encrypted_password = store
+            .put(id, SecretKind::Password, &config.password)
+            .await?
+            .unwrap_or_default();
+        let encrypted_ssh_secret = match config.ssh_tunnel.as_ref().and_then(|tunnel| tunnel.secret()) {
+            Some(secret) => store.put(id, SecretKind::SshSecret, secret).await?,
+            None => {
+                store.delete(id, SecretKind::SshSecret).await?;
+                None
+            }
+        };
+        Ok((encrypted_password, encrypted_ssh_secret))
+    }
+
+    /// Remove a connection's secrets from its backend.
+    ///
+    /// An unreachable keyring only gets a warning - it shouldn't stop a
+    /// connection from being deleted or changed.
+    async fn forget_secrets(&self, saved: &SavedConnection) {
+        let store = self.store_for(saved.credential_backend);
+        for kind in [SecretKind::Password, SecretKind::SshSecret] {
+            if let Err(e) = store.delete(&saved.id, kind).await {
+                warn!("Couldn't remove the {:?} of connection '{}': {}", kind, saved.id, e);
+            }
+        }
     }
 
     pub async fn save(&self, config: &ConnectionConfig) -> Result<SavedConnection, AppError> {
         let id = Uuid::new_v4().to_string();
-        let encrypted_password = self.encryptor.encrypt(&config.password)?;
-        let driver_str = match config.driver {
-            DatabaseDriver::MySQL => "mysql",
-            DatabaseDriver::PostgreSQL => "postgresql",
-        };
+        let (encrypted_password, encrypted_ssh_secret) = self.store_secrets(&id, config).await?;
 
         sqlx::query(
             r#"
-            INSERT INTO connections (id, name, host, port, username, encrypted_password, database, driver)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO connections (id, name, host, port, username, encrypted_password, database, driver, file_path, query_timeout_ms, max_rows,
+                                     ssh_tunnel, encrypted_ssh_secret, tls, driver_params, credential_backend, record_history)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -161,7 +329,16 @@ impl ConnectionStorage {
         .bind(&config.username)
         .bind(&encrypted_password)
         .bind(&config.database)
-        .bind(driver_str)
+        .bind(driver_to_str(config.driver))
+        .bind(&config.file_path)
+        .bind(config.limits.timeout_ms.map(|v| v as i64))
+        .bind(config.limits.max_rows.map(|v| v as i64))
+        .bind(ssh_tunnel_to_json(config)?)
+        .bind(&encrypted_ssh_secret)
+        .bind(tls_to_json(config)?)
+        .bind(params_to_json(config)?)
+        .bind(config.credential_backend.as_str())
+        .bind(config.record_history)
         .execute(&self.pool)
         .await?;
 
@@ -173,21 +350,33 @@ impl ConnectionStorage {
             username: config.username.clone(),
             database: config.database.clone(),
             driver: config.driver,
+            file_path: config.file_path.clone(),
+            limits: config.limits,
+            ssh_tunnel: config.ssh_tunnel.as_ref().map(SshTunnelConfig::without_secret),
+            tls: config.tls.clone(),
+            params: config.params.clone(),
+            credential_backend: config.credential_backend,
+            record_history: config.record_history,
             encrypted_password,
+            encrypted_ssh_secret,
         })
     }
 
     pub async fn update(&self, id: &str, config: &ConnectionConfig) -> Result<(), AppError> {
-        let encrypted_password = self.encryptor.encrypt(&config.password)?;
-        let driver_str = match config.driver {
-            DatabaseDriver::MySQL => "mysql",
-            DatabaseDriver::PostgreSQL => "postgresql",
-        };
+        // Moving to another backend leaves nothing behind in the old one
+        if let Some(previous) = self.get(id).await? {
+            if previous.credential_backend != config.credential_backend {
+                self.forget_secrets(&previous).await;
+            }
+        }
+        let (encrypted_password, encrypted_ssh_secret) = self.store_secrets(id, config).await?;
 
         sqlx::query(
             r#"
             UPDATE connections
-            SET name = ?, host = ?, port = ?, username = ?, encrypted_password = ?, database = ?, driver = ?, updated_at = CURRENT_TIMESTAMP
+            SET name = ?, host = ?, port = ?, username = ?, encrypted_password = ?, database = ?, driver = ?, file_path = ?,
+                query_timeout_ms = ?, max_rows = ?, ssh_tunnel = ?, encrypted_ssh_secret = ?, tls = ?, driver_params = ?,
+                credential_backend = ?, record_history = ?, updated_at = CURRENT_TIMESTAMP
             WHERE id = ?
             "#,
         )
@@ -197,7 +386,16 @@ impl ConnectionStorage {
         .bind(&config.username)
         .bind(&encrypted_password)
         .bind(&config.database)
-        .bind(driver_str)
+        .bind(driver_to_str(config.driver))
+        .bind(&config.file_path)
+        .bind(config.limits.timeout_ms.map(|v| v as i64))
+        .bind(config.limits.max_rows.map(|v| v as i64))
+        .bind(ssh_tunnel_to_json(config)?)
+        .bind(&encrypted_ssh_secret)
+        .bind(tls_to_json(config)?)
+        .bind(params_to_json(config)?)
+        .bind(config.credential_backend.as_str())
+        .bind(config.record_history)
         .bind(id)
         .execute(&self.pool)
         .await?;
@@ -206,76 +404,33 @@ impl ConnectionStorage {
     }
 
     pub async fn list(&self) -> Result<Vec<SavedConnection>, AppError> {
-        let rows = sqlx::query(
-            r#"
-            SELECT id, name, host, port, username, encrypted_password, database, driver
-            FROM connections
-            ORDER BY name
-            "#,
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM connections ORDER BY name",
+            SELECT_COLUMNS
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        let connections = rows
-            .iter()
-            .map(|row| {
-                let driver_str: String = row.get(7);
-                let driver = match driver_str.as_str() {
-                    "mysql" => DatabaseDriver::MySQL,
-                    "postgresql" => DatabaseDriver::PostgreSQL,
-                    _ => DatabaseDriver::MySQL,
-                };
-
-                SavedConnection {
-                    id: row.get(0),
-                    name: row.get(1),
-                    host: row.get(2),
-                    port: row.get::<i32, _>(3) as u16,
-                    username: row.get(4),
-                    encrypted_password: row.get(5),
-                    database: row.get(6),
-                    driver,
-                }
-            })
-            .collect();
-
-        Ok(connections)
+        Ok(rows.iter().map(row_to_saved_connection).collect())
     }
 
     pub async fn get(&self, id: &str) -> Result<Option<SavedConnection>, AppError> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, name, host, port, username, encrypted_password, database, driver
-            FROM connections
-            WHERE id = ?
-            "#,
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM connections WHERE id = ?",
+            SELECT_COLUMNS
+        ))
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|row| {
-            let driver_str: String = row.get(7);
-            let driver = match driver_str.as_str() {
-                "mysql" => DatabaseDriver::MySQL,
-                "postgresql" => DatabaseDriver::PostgreSQL,
-                _ => DatabaseDriver::MySQL,
-            };
-
-            SavedConnection {
-                id: row.get(0),
-                name: row.get(1),
-                host: row.get(2),
-                port: row.get::<i32, _>(3) as u16,
-                username: row.get(4),
-                encrypted_password: row.get(5),
-                database: row.get(6),
-                driver,
-            }
-        }))
+        Ok(row.as_ref().map(row_to_saved_connection))
     }
 
     pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        if let Some(saved) = self.get(id).await? {
+            self.forget_secrets(&saved).await;
+        }
+
         sqlx::query("DELETE FROM connections WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
@@ -285,10 +440,35 @@ impl ConnectionStorage {
     }
 
     pub fn decrypt_password(&self, encrypted: &[u8]) -> Result<String, AppError> {
-        self.encryptor.decrypt(encrypted)
+        self.vault.encryptor()?.decrypt(encrypted)
+    }
+
+    /// Fetch the password and SSH secret from the connection's backend 🗝️
+    ///
+    /// With the `Prompt` backend the password comes back empty and the SSH
+    /// secret as `None` - the frontend asks for them before connecting.
+    pub async fn load_secrets(&self, saved: &SavedConnection) -> Result<(String, Option<String>), AppError> {
+        let store = self.store_for(saved.credential_backend);
+        let password = store
+            .get(&saved.id, SecretKind::Password, Some(&saved.encrypted_password))
+            .await?
+            .unwrap_or_default();
+        let ssh_secret = match &saved.ssh_tunnel {
+            Some(_) => {
+                store
+                    .get(&saved.id, SecretKind::SshSecret, saved.encrypted_ssh_secret.as_deref())
+                    .await?
+            }
+            None => None,
+        };
+        Ok((password, ssh_secret))
     }
 
     pub async fn clear_all(&self) -> Result<(), AppError> {
+        for saved in self.list().await? {
+            self.forget_secrets(&saved).await;
+        }
+
         sqlx::query("DELETE FROM connections")
             .execute(&self.pool)
             .await?;
@@ -322,6 +502,13 @@ mod tests {
             password: "testpass".to_string(),
             database: Some("testdb".to_string()),
             driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         // Save connection
@@ -357,6 +544,13 @@ mod tests {
             password: "pass1".to_string(),
             database: None,
             driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         let config2 = ConnectionConfig {
@@ -367,6 +561,13 @@ mod tests {
             password: "pass2".to_string(),
             database: Some("db2".to_string()),
             driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         storage.save(&config1).await.unwrap();
@@ -389,6 +590,13 @@ mod tests {
             password: "pass".to_string(),
             database: None,
             driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         let saved = storage.save(&config).await.unwrap();
@@ -402,6 +610,13 @@ mod tests {
             password: "newpass".to_string(),
             database: Some("newdb".to_string()),
             driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         storage.update(&saved.id, &updated_config).await.unwrap();
@@ -427,6 +642,13 @@ mod tests {
             password: "pass".to_string(),
             database: None,
             driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         let saved = storage.save(&config).await.unwrap();
@@ -454,6 +676,13 @@ mod tests {
             password: "my_secret_password".to_string(),
             database: None,
             driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
         };
 
         let saved = storage.save(&config).await.unwrap();
@@ -482,6 +711,13 @@ mod tests {
                 password: "pass".to_string(),
                 database: None,
                 driver: DatabaseDriver::MySQL,
+                file_path: None,
+                limits: QueryLimits::default(),
+                ssh_tunnel: None,
+                tls: TlsConfig::default(),
+                params: BTreeMap::new(),
+                credential_backend: Default::default(),
+                record_history: true,
             };
             storage.save(&config).await.unwrap();
         }
@@ -495,4 +731,184 @@ mod tests {
         // Verify all cleared
         assert_eq!(storage.list().await.unwrap().len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_sqlite_connection_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+
+        let config = ConnectionConfig {
+            name: "Fixtures".to_string(),
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database: None,
+            driver: DatabaseDriver::SQLite,
+            file_path: Some("/tmp/fixtures.db".to_string()),
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        };
+
+        let saved = storage.save(&config).await.unwrap();
+        let retrieved = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.driver, DatabaseDriver::SQLite);
+        assert_eq!(retrieved.file_path.as_deref(), Some("/tmp/fixtures.db"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+
+        let mut config = ConnectionConfig {
+            name: "Limited".to_string(),
+            host: "localhost".to_string(),
+            port: 5432,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            database: None,
+            driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits {
+                timeout_ms: Some(30_000),
+                max_rows: None,
+            },
+            ssh_tunnel: None,
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        };
+
+        let saved = storage.save(&config).await.unwrap();
+        let retrieved = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.limits, config.limits);
+
+        config.limits.max_rows = Some(1000);
+        storage.update(&saved.id, &config).await.unwrap();
+        let retrieved = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.to_config(String::new(), None).limits.max_rows, Some(1000));
+    }
+
+    #[tokio::test]
+    async fn test_existing_database_gains_new_columns() {
+        let temp_dir = tempdir().unwrap();
+
+        // Simulate a connections.db created before file_path existed
+        {
+            let db_path = temp_dir.path().join("connections.db");
+            let pool = SqlitePoolOptions::new()
+                .connect(&format!("sqlite:{}?mode=rwc", db_path.display()))
+                .await
+                .unwrap();
+            sqlx::query(
+                r#"
+                CREATE TABLE connections (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    username TEXT NOT NULL,
+                    encrypted_password BLOB NOT NULL,
+                    database TEXT,
+                    driver TEXT NOT NULL DEFAULT 'mysql'
+                )
+                "#,
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            pool.close().await;
+        }
+
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        assert!(storage.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ssh_tunnel_secret_is_encrypted() {
+        use crate::db::tunnel::SshAuth;
+
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+
+        let tunnel = SshTunnelConfig {
+            host: "bastion.internal".to_string(),
+            port: 2222,
+            username: "deploy".to_string(),
+            auth: SshAuth::PrivateKey {
+                key_path: "~/.ssh/id_ed25519".to_string(),
+                passphrase: Some("key passphrase".to_string()),
+            },
+            known_hosts_path: None,
+        };
+        let config = ConnectionConfig {
+            name: "Behind bastion".to_string(),
+            host: "db.internal".to_string(),
+            port: 5432,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            database: None,
+            driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: Some(tunnel.clone()),
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        };
+
+        let saved = storage.save(&config).await.unwrap();
+        let retrieved = storage.get(&saved.id).await.unwrap().unwrap();
+
+        // The stored settings have no passphrase, and it isn't sent to the frontend
+        let stored = retrieved.ssh_tunnel.clone().unwrap();
+        assert_eq!(stored.secret(), None);
+        assert!(!serde_json::to_string(&retrieved).unwrap().contains("key passphrase"));
+
+        let encrypted = retrieved.encrypted_ssh_secret.clone().unwrap();
+        let secret = storage.decrypt_password(&encrypted).unwrap();
+        let restored = retrieved.to_config("pass".to_string(), Some(secret));
+        assert_eq!(restored.ssh_tunnel, Some(tunnel));
+    }
+
+    #[tokio::test]
+    async fn test_tls_round_trip() {
+        use crate::db::tls::TlsMode;
+
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+
+        let config = ConnectionConfig {
+            name: "Verified".to_string(),
+            host: "db.example.com".to_string(),
+            port: 3306,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            database: None,
+            driver: DatabaseDriver::MySQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: None,
+            tls: TlsConfig {
+                mode: TlsMode::VerifyFull,
+                ca_cert_path: Some("/etc/ssl/db-ca.pem".to_string()),
+                client_cert_path: Some("/etc/ssl/client.pem".to_string()),
+                client_key_path: Some("/etc/ssl/client.key".to_string()),
+            },
+            params: BTreeMap::new(),
+            credential_backend: Default::default(),
+            record_history: true,
+        };
+
+        let saved = storage.save(&config).await.unwrap();
+        let retrieved = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.tls, config.tls);
+    }
 }
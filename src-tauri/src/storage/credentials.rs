@@ -0,0 +1,392 @@
+//! Where saved connection secrets live! 🗝️✨
+//!
+//! Each saved connection picks a credential backend for its password and
+//! SSH secret:
+//!
+//! - **Encrypted** (default): AES-256-GCM in Anko's own database, keyed by
+//!   the machine ID or the master password (see `vault`)
+//! - **Keyring**: the OS keyring through the Secret Service D-Bus API
+//!   (GNOME Keyring, KWallet, KeePassXC, ...) - nothing lands in app files
+//! - **Prompt**: never stored at all, asked for on every connect
+//!
+//! All of them sit behind the `CredentialStore` trait, so `ConnectionStorage`
+//! doesn't care which one a connection uses~ 🌟
+
+use async_trait::async_trait;
+use secret_service::{EncryptionType, SecretService};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::storage::vault::CredentialVault;
+
+/// Which backend keeps a connection's secrets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialBackend {
+    /// Encrypted in Anko's database
+    #[default]
+    Encrypted,
+    /// The OS keyring (Secret Service)
+    Keyring,
+    /// Not stored - the frontend asks on connect
+    Prompt,
+}
+
+impl CredentialBackend {
+    /// Storage representation (the `credential_backend` column)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CredentialBackend::Encrypted => "encrypted",
+            CredentialBackend::Keyring => "keyring",
+            CredentialBackend::Prompt => "prompt",
+        }
+    }
+
+    /// Parse the `credential_backend` column, falling back to `Encrypted` for unknown or missing values
+    pub fn from_column(value: Option<&str>) -> Self {
+        match value {
+            Some("keyring") => CredentialBackend::Keyring,
+            Some("prompt") => CredentialBackend::Prompt,
+            _ => CredentialBackend::Encrypted,
+        }
+    }
+}
+
+/// Which of a connection's secrets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretKind {
+    /// The database password
+    Password,
+    /// The SSH password or key passphrase
+    SshSecret,
+}
+
+impl SecretKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SecretKind::Password => "password",
+            SecretKind::SshSecret => "ssh_secret",
+        }
+    }
+}
+
+/// A place to keep connection secrets 🗝️
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    /// Store a secret, returning the bytes to keep in the connection's row (if any)
+    async fn put(&self, connection_id: &str, kind: SecretKind, secret: &str) -> Result<Option<Vec<u8>>, AppError>;
+
+    /// Get a secret back, given what `put` returned for the row
+    ///
+    /// `Ok(None)` means there is no such secret.
+    async fn get(&self, connection_id: &str, kind: SecretKind, stored: Option<&[u8]>)
+        -> Result<Option<String>, AppError>;
+
+    /// Forget a secret (no-op if there is none)
+    async fn delete(&self, connection_id: &str, kind: SecretKind) -> Result<(), AppError>;
+}
+
+/// Secrets encrypted into the connection's row (the original behaviour)
+pub struct EncryptedStore {
+    vault: Arc<CredentialVault>,
+}
+
+impl EncryptedStore {
+    pub fn new(vault: Arc<CredentialVault>) -> Self {
+        Self { vault }
+    }
+}
+
+#[async_trait]
+impl CredentialStore for EncryptedStore {
+    async fn put(&self, _connection_id: &str, _kind: SecretKind, secret: &str) -> Result<Option<Vec<u8>>, AppError> {
+        Ok(Some(self.vault.encryptor()?.encrypt(secret)?))
+    }
+
+    async fn get(
+        &self,
+        _connection_id: &str,
+        _kind: SecretKind,
+        stored: Option<&[u8]>,
+    ) -> Result<Option<String>, AppError> {
+        stored.map(|data| self.vault.encryptor()?.decrypt(data)).transpose()
+    }
+
+    async fn delete(&self, _connection_id: &str, _kind: SecretKind) -> Result<(), AppError> {
+        // Lives in the row, which goes away with the connection
+        Ok(())
+    }
+}
+
+/// Nothing is stored - the password is asked for on connect
+pub struct PromptStore;
+
+#[async_trait]
+impl CredentialStore for PromptStore {
+    async fn put(&self, _connection_id: &str, _kind: SecretKind, _secret: &str) -> Result<Option<Vec<u8>>, AppError> {
+        Ok(None)
+    }
+
+    async fn get(
+        &self,
+        _connection_id: &str,
+        _kind: SecretKind,
+        _stored: Option<&[u8]>,
+    ) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+
+    async fn delete(&self, _connection_id: &str, _kind: SecretKind) -> Result<(), AppError> {
+        Ok(())
+    }
+}
+
+/// Attribute marking keyring items as ours
+const KEYRING_APPLICATION: &str = "anko";
+
+fn keyring_error(e: secret_service::Error) -> AppError {
+    AppError::Keyring(e.to_string())
+}
+
+/// The OS keyring through the Secret Service D-Bus API 🔑
+///
+/// Items live in the default collection, found by their `application`,
+/// `connection_id` and `secret` attributes. A locked collection triggers the
+/// keyring's own unlock prompt.
+pub struct SecretServiceStore;
+
+impl SecretServiceStore {
+    fn attributes(connection_id: &str, kind: SecretKind) -> HashMap<&str, &str> {
+        HashMap::from([
+            ("application", KEYRING_APPLICATION),
+            ("connection_id", connection_id),
+            ("secret", kind.as_str()),
+        ])
+    }
+
+    async fn connect() -> Result<SecretService<'static>, AppError> {
+        SecretService::connect(EncryptionType::Dh).await.map_err(keyring_error)
+    }
+}
+
+#[async_trait]
+impl CredentialStore for SecretServiceStore {
+    async fn put(&self, connection_id: &str, kind: SecretKind, secret: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let service = Self::connect().await?;
+        let collection = service.get_default_collection().await.map_err(keyring_error)?;
+        collection.ensure_unlocked().await.map_err(keyring_error)?;
+        collection
+            .create_item(
+                &format!("Anko {} for connection {}", kind.as_str().replace('_', " "), connection_id),
+                Self::attributes(connection_id, kind),
+                secret.as_bytes(),
+                true,
+                "text/plain",
+            )
+            .await
+            .map_err(keyring_error)?;
+        Ok(None)
+    }
+
+    async fn get(
+        &self,
+        connection_id: &str,
+        kind: SecretKind,
+        _stored: Option<&[u8]>,
+    ) -> Result<Option<String>, AppError> {
+        let service = Self::connect().await?;
+        let found = service
+            .search_items(Self::attributes(connection_id, kind))
+            .await
+            .map_err(keyring_error)?;
+        let Some(item) = found.unlocked.first().or(found.locked.first()) else {
+            return Ok(None);
+        };
+        item.ensure_unlocked().await.map_err(keyring_error)?;
+        let secret = item.get_secret().await.map_err(keyring_error)?;
+        String::from_utf8(secret)
+            .map(Some)
+            .map_err(|e| AppError::Keyring(format!("Stored secret is not valid UTF-8: {}", e)))
+    }
+
+    async fn delete(&self, connection_id: &str, kind: SecretKind) -> Result<(), AppError> {
+        let service = Self::connect().await?;
+        let found = service
+            .search_items(Self::attributes(connection_id, kind))
+            .await
+            .map_err(keyring_error)?;
+        for item in found.unlocked.iter().chain(found.locked.iter()) {
+            item.delete().await.map_err(keyring_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// An in-memory keyring standing in for the Secret Service in tests
+#[cfg(test)]
+#[derive(Default, Clone)]
+pub struct MockKeyring {
+    secrets: Arc<std::sync::Mutex<HashMap<(String, SecretKind), String>>>,
+}
+
+#[cfg(test)]
+impl MockKeyring {
+    pub fn len(&self) -> usize {
+        self.secrets.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl CredentialStore for MockKeyring {
+    async fn put(&self, connection_id: &str, kind: SecretKind, secret: &str) -> Result<Option<Vec<u8>>, AppError> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert((connection_id.to_string(), kind), secret.to_string());
+        Ok(None)
+    }
+
+    async fn get(
+        &self,
+        connection_id: &str,
+        kind: SecretKind,
+        _stored: Option<&[u8]>,
+    ) -> Result<Option<String>, AppError> {
+        Ok(self.secrets.lock().unwrap().get(&(connection_id.to_string(), kind)).cloned())
+    }
+
+    async fn delete(&self, connection_id: &str, kind: SecretKind) -> Result<(), AppError> {
+        self.secrets.lock().unwrap().remove(&(connection_id.to_string(), kind));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connector::{ConnectionConfig, DatabaseDriver, QueryLimits};
+    use crate::db::tls::TlsConfig;
+    use crate::db::tunnel::{SshAuth, SshTunnelConfig};
+    use crate::storage::ConnectionStorage;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn config(credential_backend: CredentialBackend) -> ConnectionConfig {
+        ConnectionConfig {
+            name: "Secret".to_string(),
+            host: "db.internal".to_string(),
+            port: 5432,
+            username: "app".to_string(),
+            password: "db_password".to_string(),
+            database: None,
+            driver: DatabaseDriver::PostgreSQL,
+            file_path: None,
+            limits: QueryLimits::default(),
+            ssh_tunnel: Some(SshTunnelConfig {
+                host: "bastion.internal".to_string(),
+                port: 22,
+                username: "deploy".to_string(),
+                auth: SshAuth::Password {
+                    password: "ssh_password".to_string(),
+                },
+                known_hosts_path: None,
+            }),
+            tls: TlsConfig::default(),
+            params: BTreeMap::new(),
+            credential_backend,
+            record_history: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keyring_backend_keeps_secrets_out_of_the_database() {
+        let temp_dir = tempdir().unwrap();
+        let keyring = MockKeyring::default();
+        let storage = ConnectionStorage::with_keyring(temp_dir.path(), Box::new(keyring.clone()))
+            .await
+            .unwrap();
+
+        let saved = storage.save(&config(CredentialBackend::Keyring)).await.unwrap();
+        let stored = storage.get(&saved.id).await.unwrap().unwrap();
+        assert!(stored.encrypted_password.is_empty());
+        assert_eq!(stored.encrypted_ssh_secret, None);
+        assert_eq!(keyring.len(), 2);
+
+        let (password, ssh_secret) = storage.load_secrets(&stored).await.unwrap();
+        assert_eq!(password, "db_password");
+        assert_eq!(ssh_secret.as_deref(), Some("ssh_password"));
+
+        // Switching back to the encrypted backend cleans up the keyring
+        storage.update(&saved.id, &config(CredentialBackend::Encrypted)).await.unwrap();
+        assert_eq!(keyring.len(), 0);
+        let stored = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(storage.load_secrets(&stored).await.unwrap().0, "db_password");
+
+        // Master password migration leaves keyring-backed rows alone
+        storage.save(&config(CredentialBackend::Keyring)).await.unwrap();
+        storage.vault().enable("master password", None).await.unwrap();
+
+        storage.delete(&saved.id).await.unwrap();
+        storage.clear_all().await.unwrap();
+        assert_eq!(keyring.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_backend_stores_nothing() {
+        let temp_dir = tempdir().unwrap();
+        let keyring = MockKeyring::default();
+        let storage = ConnectionStorage::with_keyring(temp_dir.path(), Box::new(keyring.clone()))
+            .await
+            .unwrap();
+
+        let saved = storage.save(&config(CredentialBackend::Prompt)).await.unwrap();
+        let stored = storage.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(stored.credential_backend, CredentialBackend::Prompt);
+        assert!(stored.encrypted_password.is_empty());
+        assert_eq!(keyring.len(), 0);
+
+        let (password, ssh_secret) = storage.load_secrets(&stored).await.unwrap();
+        assert_eq!(password, "");
+        assert_eq!(ssh_secret, None);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_secret_service_round_trip() {
+        // This requires a running Secret Service with an unlocked default collection, e.g.
+        // dbus-run-session -- sh -c 'echo -n test | gnome-keyring-daemon --unlock --components=secrets
+        //   && cargo test -- --ignored test_secret_service_round_trip'
+        let store = SecretServiceStore;
+        let connection_id = uuid::Uuid::new_v4().to_string();
+
+        assert_eq!(store.put(&connection_id, SecretKind::Password, "db_password").await.unwrap(), None);
+        store.put(&connection_id, SecretKind::SshSecret, "ssh_password").await.unwrap();
+        assert_eq!(
+            store.get(&connection_id, SecretKind::Password, None).await.unwrap().as_deref(),
+            Some("db_password")
+        );
+        assert_eq!(
+            store.get(&connection_id, SecretKind::SshSecret, None).await.unwrap().as_deref(),
+            Some("ssh_password")
+        );
+
+        // Storing again replaces the item instead of adding a second one
+        store.put(&connection_id, SecretKind::Password, "rotated").await.unwrap();
+        assert_eq!(
+            store.get(&connection_id, SecretKind::Password, None).await.unwrap().as_deref(),
+            Some("rotated")
+        );
+
+        store.delete(&connection_id, SecretKind::Password).await.unwrap();
+        assert_eq!(store.get(&connection_id, SecretKind::Password, None).await.unwrap(), None);
+        assert!(store.get(&connection_id, SecretKind::SshSecret, None).await.unwrap().is_some());
+
+        // Deleting what isn't there is fine
+        store.delete(&connection_id, SecretKind::Password).await.unwrap();
+        store.delete(&connection_id, SecretKind::SshSecret).await.unwrap();
+        assert_eq!(store.get(&connection_id, SecretKind::SshSecret, None).await.unwrap(), None);
+    }
+}
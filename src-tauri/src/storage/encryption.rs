@@ -8,6 +8,8 @@
 //! **PBKDF2 Key Derivation**
 //! - Derives key from machine ID with 100,000 iterations
 //! - Deterministic: same machine = same key (reliable across app restarts)
+//! - Export files use a key derived from the user's passphrase and a random
+//!   salt instead, so they can be opened on another machine
 //!
 //! Each encrypted password includes a random 12-byte nonce to ensure that
 //! identical passwords encrypt to different ciphertexts.
@@ -35,6 +37,8 @@ use crate::error::AppError;
 const KEY_SIZE: usize = 32;
 /// Size of GCM nonce in bytes (96 bits = 12 bytes, as per GCM spec)
 const NONCE_SIZE: usize = 12;
+/// Size of the random salt for passphrase-derived keys (128 bits = 16 bytes)
+const SALT_SIZE: usize = 16;
 
 /// AES-256-GCM password encryptor with PBKDF2 key derivation.
 ///
@@ -52,7 +56,30 @@ impl Encryptor {
     /// 100,000 iterations. This is deterministic: same machine = same key.
     pub fn new() -> Result<Self, AppError> {
         let key = Self::derive_key()?;
-        let cipher = Aes256Gcm::new_from_slice(&key)
+        Self::from_key(&key)
+    }
+
+    /// Create an Encryptor keyed by a passphrase instead of the machine ID.
+    ///
+    /// The same passphrase and salt always give the same key, on any machine.
+    /// `salt` is a B64 salt string, as made by `generate_salt`.
+    pub fn with_passphrase(passphrase: &str, salt: &str) -> Result<Self, AppError> {
+        let salt = SaltString::from_b64(salt)
+            .map_err(|e| AppError::Encryption(format!("Invalid salt: {}", e)))?;
+        let key = Self::derive_key_from(passphrase.as_bytes(), &salt)?;
+        Self::from_key(&key)
+    }
+
+    /// A fresh random salt for `with_passphrase`, as a B64 string.
+    pub fn generate_salt() -> Result<String, AppError> {
+        let salt_bytes: [u8; SALT_SIZE] = rand::thread_rng().gen();
+        SaltString::encode_b64(&salt_bytes)
+            .map(|salt| salt.as_str().to_string())
+            .map_err(|e| AppError::Encryption(format!("Failed to create salt: {}", e)))
+    }
+
+    fn from_key(key: &[u8; KEY_SIZE]) -> Result<Self, AppError> {
+        let cipher = Aes256Gcm::new_from_slice(key)
             .map_err(|e| AppError::Encryption(e.to_string()))?;
         Ok(Self { cipher })
     }
@@ -108,8 +135,13 @@ impl Encryptor {
         let salt = SaltString::encode_b64(b"anko-sql-client-salt-v1-do-not-change")
             .map_err(|e| AppError::Encryption(format!("Failed to create salt: {}", e)))?;
 
+        Self::derive_key_from(machine_id.as_bytes(), &salt)
+    }
+
+    /// Derives a key from any secret and salt using PBKDF2.
+    fn derive_key_from(secret: &[u8], salt: &SaltString) -> Result<[u8; KEY_SIZE], AppError> {
         let hash = Pbkdf2
-            .hash_password(machine_id.as_bytes(), &salt)
+            .hash_password(secret, salt)
             .map_err(|e| AppError::Encryption(format!("PBKDF2 failed: {}", e)))?;
 
         let hash_output = hash
@@ -209,4 +241,19 @@ mod tests {
         assert!(result.is_err(), "Should fail with garbage data");
     }
 
+    #[test]
+    fn test_passphrase_key() {
+        let salt = Encryptor::generate_salt().unwrap();
+        let encrypted = Encryptor::with_passphrase("correct horse", &salt)
+            .unwrap()
+            .encrypt("db_password")
+            .unwrap();
+
+        // Same passphrase and salt decrypt, a different passphrase doesn't
+        let same = Encryptor::with_passphrase("correct horse", &salt).unwrap();
+        assert_eq!(same.decrypt(&encrypted).unwrap(), "db_password");
+        let other = Encryptor::with_passphrase("battery staple", &salt).unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
 }
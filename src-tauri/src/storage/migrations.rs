@@ -0,0 +1,513 @@
+//! Versioned schema migrations for the app's SQLite storage! 🧬✨
+//!
+//! Every change to the tables is a numbered migration below, applied in
+//! order and recorded in `schema_version`, so an existing `connections.db`
+//! gets exactly the changes it's missing - no more silently absent columns.
+//!
+//! Rules for adding one:
+//!
+//! - Append it with the next version number - never edit or reorder old ones
+//! - Each migration runs in its own transaction together with its
+//!   `schema_version` row, so it applies completely or not at all
+//! - Adding a column goes through `Step::AddColumn`, which skips columns that
+//!   already exist (databases from before this runner added some on their own)
+//!
+//! Before migrating an existing database, a copy is saved next to it as
+//! `connections-backup-v<version>.db`~ 💾
+
+use log::{info, warn};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::path::Path;
+
+use crate::error::AppError;
+
+/// One change to the schema
+enum Step {
+    /// Any SQL statement
+    Sql(&'static str),
+    /// `ALTER TABLE ... ADD COLUMN`, unless the column is already there
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+/// All migrations, oldest first
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Connections, workspaces, query history and saved queries",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS connections (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    username TEXT NOT NULL,
+                    encrypted_password BLOB NOT NULL,
+                    database TEXT,
+                    driver TEXT NOT NULL DEFAULT 'mysql',
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS workspaces (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    icon TEXT NOT NULL DEFAULT 'database',
+                    is_default INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS workspace_connections (
+                    workspace_id TEXT NOT NULL,
+                    connection_id TEXT NOT NULL,
+                    added_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (workspace_id, connection_id),
+                    FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT OR IGNORE INTO workspaces (id, name, icon, is_default)
+                VALUES ('default', 'Default', 'database', 1)
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS query_history (
+                    id TEXT PRIMARY KEY,
+                    query TEXT NOT NULL,
+                    connection_id TEXT NOT NULL,
+                    connection_name TEXT NOT NULL,
+                    database_name TEXT,
+                    executed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    execution_time_ms INTEGER,
+                    row_count INTEGER,
+                    success INTEGER NOT NULL DEFAULT 1,
+                    error_message TEXT
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_query_history_executed_at ON query_history(executed_at)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS saved_queries (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    query TEXT NOT NULL,
+                    description TEXT,
+                    workspace_id TEXT,
+                    connection_id TEXT,
+                    database_name TEXT,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE SET NULL
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_saved_queries_workspace ON saved_queries(workspace_id)"),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "SQLite connections",
+        steps: &[Step::AddColumn {
+            table: "connections",
+            column: "file_path",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 3,
+        description: "Per-connection query timeout and row limit",
+        steps: &[
+            Step::AddColumn {
+                table: "connections",
+                column: "query_timeout_ms",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "connections",
+                column: "max_rows",
+                definition: "INTEGER",
+            },
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "SSH tunnels",
+        steps: &[
+            Step::AddColumn {
+                table: "connections",
+                column: "ssh_tunnel",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "connections",
+                column: "encrypted_ssh_secret",
+                definition: "BLOB",
+            },
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "TLS settings",
+        steps: &[Step::AddColumn {
+            table: "connections",
+            column: "tls",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 6,
+        description: "Driver parameters",
+        steps: &[Step::AddColumn {
+            table: "connections",
+            column: "driver_params",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 7,
+        description: "Master password",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS master_password (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                salt TEXT NOT NULL,
+                verifier BLOB NOT NULL,
+                auto_lock_minutes INTEGER
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 8,
+        description: "Credential backends",
+        steps: &[Step::AddColumn {
+            table: "connections",
+            column: "credential_backend",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 9,
+        description: "Query history full-text search",
+        steps: &[
+            // External-content index: the text lives in query_history, keyed by its rowid
+            Step::Sql(
+                r#"
+                CREATE VIRTUAL TABLE IF NOT EXISTS query_history_fts USING fts5(
+                    query,
+                    content = 'query_history',
+                    content_rowid = 'rowid'
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS query_history_fts_insert AFTER INSERT ON query_history BEGIN
+                    INSERT INTO query_history_fts (rowid, query) VALUES (new.rowid, new.query);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS query_history_fts_delete AFTER DELETE ON query_history BEGIN
+                    INSERT INTO query_history_fts (query_history_fts, rowid, query) VALUES ('delete', old.rowid, old.query);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS query_history_fts_update AFTER UPDATE OF query ON query_history BEGIN
+                    INSERT INTO query_history_fts (query_history_fts, rowid, query) VALUES ('delete', old.rowid, old.query);
+                    INSERT INTO query_history_fts (rowid, query) VALUES (new.rowid, new.query);
+                END
+                "#,
+            ),
+            // Index whatever history is already there
+            Step::Sql("INSERT INTO query_history_fts (query_history_fts) VALUES ('rebuild')"),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_query_history_connection ON query_history(connection_id, executed_at)",
+            ),
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "History retention per workspace, run counts and pinning",
+        steps: &[
+            Step::AddColumn {
+                table: "workspaces",
+                column: "history_retention_days",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "workspaces",
+                column: "history_max_entries",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "query_history",
+                column: "run_count",
+                definition: "INTEGER NOT NULL DEFAULT 1",
+            },
+            Step::AddColumn {
+                table: "query_history",
+                column: "last_run_at",
+                definition: "DATETIME",
+            },
+            Step::AddColumn {
+                table: "query_history",
+                column: "pinned",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::Sql("UPDATE query_history SET last_run_at = executed_at WHERE last_run_at IS NULL"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_query_history_last_run_at ON query_history(last_run_at)"),
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "Automatic history recording",
+        steps: &[
+            Step::AddColumn {
+                table: "connections",
+                column: "record_history",
+                definition: "INTEGER NOT NULL DEFAULT 1",
+            },
+            Step::AddColumn {
+                table: "query_history",
+                column: "schema_name",
+                definition: "TEXT",
+            },
+        ],
+    },
+];
+
+/// The version a fully migrated database is at
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// The database's current schema version (0 if never migrated)
+pub async fn current_version(pool: &Pool<Sqlite>) -> Result<i64, AppError> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Bring the database up to the latest schema version 🚀
+///
+/// # Arguments
+/// * `pool` - Pool on the database to migrate
+/// * `db_path` - Path of the database file, for the pre-migration backup
+///
+/// # Errors
+/// Returns `AppError` if the backup or a migration fails. A failed
+/// migration is rolled back, leaving the database at the previous version.
+pub async fn run(pool: &Pool<Sqlite>, db_path: &Path) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let current = current_version(pool).await?;
+    if current > latest_version() {
+        warn!(
+            "Storage schema is at version {}, newer than this app knows ({})",
+            current,
+            latest_version()
+        );
+        return Ok(());
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    if has_data(pool).await? {
+        backup(pool, db_path, current).await?;
+    }
+
+    for migration in pending {
+        let mut tx = pool.begin().await?;
+        for step in migration.steps {
+            apply(&mut tx, step).await?;
+        }
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        info!("Applied storage migration {}: {}", migration.version, migration.description);
+    }
+
+    Ok(())
+}
+
+/// True if there's an existing database worth backing up (not a fresh install)
+async fn has_data(pool: &Pool<Sqlite>) -> Result<bool, AppError> {
+    let tables: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT IN ('schema_version', 'sqlite_sequence')",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(tables > 0)
+}
+
+/// Write a consistent copy of the database next to it before migrating 💾
+async fn backup(pool: &Pool<Sqlite>, db_path: &Path, version: i64) -> Result<(), AppError> {
+    let backup_path = db_path.with_file_name(format!("connections-backup-v{}.db", version));
+    if backup_path.exists() {
+        std::fs::remove_file(&backup_path)?;
+    }
+
+    // VACUUM INTO gives a consistent snapshot even with pending WAL pages
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.display().to_string())
+        .execute(pool)
+        .await?;
+    info!("Backed up storage to {} before migrating", backup_path.display());
+    Ok(())
+}
+
+async fn apply(conn: &mut SqliteConnection, step: &Step) -> Result<(), AppError> {
+    match step {
+        Step::Sql(sql) => {
+            sqlx::query(sql).execute(&mut *conn).await?;
+        }
+        Step::AddColumn {
+            table,
+            column,
+            definition,
+        } => {
+            let exists: bool =
+                sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?")
+                    .bind(table)
+                    .bind(column)
+                    .fetch_one(&mut *conn)
+                    .await?;
+            if !exists {
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tempfile::tempdir;
+
+    async fn open(path: &Path) -> Pool<Sqlite> {
+        SqlitePoolOptions::new()
+            .connect(&format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_versions_are_ascending() {
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[1].version, pair[0].version + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fresh_database_has_no_backup() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("connections.db");
+        let pool = open(&db_path).await;
+
+        run(&pool, &db_path).await.unwrap();
+        assert_eq!(current_version(&pool).await.unwrap(), latest_version());
+        assert!(!temp_dir.path().join("connections-backup-v0.db").exists());
+
+        // Running again is a no-op
+        run(&pool, &db_path).await.unwrap();
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_version")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, latest_version());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_database_is_backed_up_and_migrated() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("connections.db");
+        let pool = open(&db_path).await;
+
+        // A database from before the migration runner, with some columns already added
+        sqlx::query(
+            r#"
+            CREATE TABLE connections (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL,
+                username TEXT NOT NULL,
+                encrypted_password BLOB NOT NULL,
+                database TEXT,
+                driver TEXT NOT NULL DEFAULT 'mysql',
+                file_path TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO connections (id, name, host, port, username, encrypted_password) VALUES ('c1', 'Old', 'localhost', 3306, 'root', x'00')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run(&pool, &db_path).await.unwrap();
+        assert_eq!(current_version(&pool).await.unwrap(), latest_version());
+
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('connections')")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert!(columns.iter().any(|c| c == "credential_backend"));
+
+        let backup = open(&temp_dir.path().join("connections-backup-v0.db")).await;
+        let names: Vec<String> = sqlx::query_scalar("SELECT name FROM connections")
+            .fetch_all(&backup)
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["Old".to_string()]);
+    }
+}
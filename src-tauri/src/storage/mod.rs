@@ -5,16 +5,26 @@
 //! All passwords are encrypted before storage using AES-256-GCM! 🔐💪
 //!
 //! Key modules:
+//! - `bundle`: Passphrase-encrypted export and import of the whole setup
 //! - `connections`: CRUD operations for saved database connections
+//! - `credentials`: Where connection secrets live (encrypted, OS keyring, or prompt)
 //! - `encryption`: AES-256-GCM password encryption with OS keychain integration
+//! - `migrations`: Versioned schema migrations with a backup before upgrading
+//! - `import`: Parse connection URLs, .pgpass, pg_service.conf, my.cnf and .env files
 //! - `workspaces`: Groups of connections for organization
 //! - `query_history`: Query execution history tracking
 //! - `saved_queries`: User-saved queries for quick access
+//! - `vault`: Optional master password that locks saved credentials
 
+pub mod bundle;
 pub mod connections;
+pub mod credentials;
 pub mod encryption;
+pub mod import;
+pub mod migrations;
 pub mod query_history;
 pub mod saved_queries;
+pub mod vault;
 pub mod workspaces;
 
 pub use connections::*;
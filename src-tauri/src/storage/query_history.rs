@@ -1,19 +1,39 @@
 //! Query history storage for tracking executed queries.
 //!
-//! Stores query execution history with automatic cleanup for entries older
-//! than 30 days and a maximum of 1000 entries to prevent unbounded growth.
+//! Stores query execution history. Running the same query again right away
+//! bumps the previous entry's run counter instead of adding a new one.
+//! Cleanup runs periodically in the background, dropping entries past their
+//! workspace's retention (30 days and 1000 entries unless configured) -
+//! except pinned ones, which stay until deleted by hand. Query text is
+//! indexed with SQLite FTS5 so history can be searched by words, alongside
+//! filters on database, outcome, date and duration.
 
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Row, Sqlite};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Pool, QueryBuilder, Row, Sqlite};
 use uuid::Uuid;
 
 use crate::error::AppError;
 
-/// Maximum number of history entries to keep
-const MAX_HISTORY_ENTRIES: i64 = 1000;
+/// Entries to keep per workspace when it doesn't set its own limit
+pub const DEFAULT_MAX_HISTORY_ENTRIES: i64 = 1000;
 
-/// Number of days to keep history entries
-const HISTORY_RETENTION_DAYS: i32 = 30;
+/// Days to keep entries when the workspace doesn't set its own retention
+pub const DEFAULT_HISTORY_RETENTION_DAYS: i64 = 30;
+
+/// Entries per search page when no limit is given
+const DEFAULT_SEARCH_PAGE_SIZE: i64 = 100;
+
+/// Largest search page
+const MAX_SEARCH_PAGE_SIZE: i64 = 1000;
+
+/// Columns read into a `QueryHistoryEntry`, in `row_to_entry` order
+const SELECT_COLUMNS: &str = r#"
+    id, query, connection_id, connection_name, database_name,
+    datetime(executed_at) as executed_at, execution_time_ms,
+    row_count, success, error_message,
+    run_count, datetime(last_run_at) as last_run_at, pinned, schema_name
+"#;
 
 /// A query history entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,11 +44,19 @@ pub struct QueryHistoryEntry {
     pub connection_id: String,
     pub connection_name: String,
     pub database_name: Option<String>,
+    /// Schema the query ran in (PostgreSQL search path), if one was set
+    pub schema_name: Option<String>,
+    /// When the query first ran
     pub executed_at: String,
     pub execution_time_ms: Option<i64>,
     pub row_count: Option<i64>,
     pub success: bool,
     pub error_message: Option<String>,
+    /// How many times in a row it ran (timing and outcome are from the last run)
+    pub run_count: i64,
+    pub last_run_at: String,
+    /// Pinned entries are never cleaned up
+    pub pinned: bool,
 }
 
 /// Input for adding a new history entry
@@ -39,12 +67,47 @@ pub struct AddQueryHistoryInput {
     pub connection_id: String,
     pub connection_name: String,
     pub database_name: Option<String>,
+    #[serde(default)]
+    pub schema_name: Option<String>,
     pub execution_time_ms: Option<i64>,
     pub row_count: Option<i64>,
     pub success: bool,
     pub error_message: Option<String>,
 }
 
+/// Filters for searching query history
+///
+/// Every filter is optional; the ones given must all match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QueryHistorySearch {
+    /// Words that must all appear in the query (each also matches as a prefix)
+    pub text: Option<String>,
+    pub connection_id: Option<String>,
+    pub database_name: Option<String>,
+    /// Only successful (`true`) or failed (`false`) runs
+    pub success: Option<bool>,
+    /// Only entries that ran at or after this time (UTC, e.g. `2024-05-14` or `2024-05-14 09:30:00`)
+    pub executed_after: Option<String>,
+    /// Only entries that ran before this time (same format as `executed_after`)
+    pub executed_before: Option<String>,
+    pub min_execution_time_ms: Option<i64>,
+    pub pinned: Option<bool>,
+    /// `next_cursor` of the previous page, to continue where it ended
+    pub cursor: Option<String>,
+    /// Page size (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+/// One page of history search results, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryHistoryPage {
+    pub entries: Vec<QueryHistoryEntry>,
+    /// Pass back as `cursor` for the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
 /// SQLite storage for query history
 pub struct QueryHistoryStorage {
     pool: Pool<Sqlite>,
@@ -55,69 +118,79 @@ impl QueryHistoryStorage {
         Self { pool }
     }
 
-    /// Initialize the query_history table schema
-    pub async fn initialize_schema(&self) -> Result<(), AppError> {
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS query_history (
-                id TEXT PRIMARY KEY,
-                query TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
-                connection_name TEXT NOT NULL,
-                database_name TEXT,
-                executed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                execution_time_ms INTEGER,
-                row_count INTEGER,
-                success INTEGER NOT NULL DEFAULT 1,
-                error_message TEXT
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create index on executed_at for faster cleanup queries
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_query_history_executed_at
-            ON query_history(executed_at)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
-    }
-
-    /// Add a new query to history with automatic cleanup
+    /// Add a query to history
+    ///
+    /// If the connection's latest entry is the same query on the same
+    /// database and schema with the same outcome, that entry's run counter and timing
+    /// are updated instead.
     pub async fn add(&self, input: &AddQueryHistoryInput) -> Result<QueryHistoryEntry, AppError> {
-        // Clean up old entries first
-        self.cleanup().await?;
-
-        let id = Uuid::new_v4().to_string();
-
-        sqlx::query(
+        let latest = sqlx::query(
             r#"
-            INSERT INTO query_history (
-                id, query, connection_id, connection_name, database_name,
-                execution_time_ms, row_count, success, error_message
-            )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            SELECT id, query, database_name, schema_name, success
+            FROM query_history
+            WHERE connection_id = ?
+            ORDER BY last_run_at DESC, rowid DESC
+            LIMIT 1
             "#,
         )
-        .bind(&id)
-        .bind(&input.query)
         .bind(&input.connection_id)
-        .bind(&input.connection_name)
-        .bind(&input.database_name)
-        .bind(input.execution_time_ms)
-        .bind(input.row_count)
-        .bind(input.success)
-        .bind(&input.error_message)
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        // Fetch the created entry to get the timestamp
+        let repeated = latest.and_then(|r| {
+            let same = r.get::<String, _>(1) == input.query
+                && r.get::<Option<String>, _>(2) == input.database_name
+                && r.get::<Option<String>, _>(3) == input.schema_name
+                && (r.get::<i32, _>(4) != 0) == input.success;
+            same.then(|| r.get::<String, _>(0))
+        });
+
+        let id = match repeated {
+            Some(id) => {
+                sqlx::query(
+                    r#"
+                    UPDATE query_history
+                    SET run_count = run_count + 1, last_run_at = CURRENT_TIMESTAMP,
+                        execution_time_ms = ?, row_count = ?, error_message = ?
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(input.execution_time_ms)
+                .bind(input.row_count)
+                .bind(&input.error_message)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    r#"
+                    INSERT INTO query_history (
+                        id, query, connection_id, connection_name, database_name, schema_name,
+                        execution_time_ms, row_count, success, error_message, last_run_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+                    "#,
+                )
+                .bind(&id)
+                .bind(&input.query)
+                .bind(&input.connection_id)
+                .bind(&input.connection_name)
+                .bind(&input.database_name)
+                .bind(&input.schema_name)
+                .bind(input.execution_time_ms)
+                .bind(input.row_count)
+                .bind(input.success)
+                .bind(&input.error_message)
+                .execute(&self.pool)
+                .await?;
+                id
+            }
+        };
+
+        // Fetch the entry to get the timestamps
         self.get(&id)
             .await?
             .ok_or_else(|| AppError::Storage("Failed to retrieve created history entry".to_string()))
@@ -125,31 +198,12 @@ impl QueryHistoryStorage {
 
     /// Get a single history entry by ID
     pub async fn get(&self, id: &str) -> Result<Option<QueryHistoryEntry>, AppError> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, query, connection_id, connection_name, database_name,
-                   datetime(executed_at) as executed_at, execution_time_ms,
-                   row_count, success, error_message
-            FROM query_history
-            WHERE id = ?
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
+        let row = sqlx::query(&format!("SELECT {} FROM query_history WHERE id = ?", SELECT_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
 
-        Ok(row.map(|r| QueryHistoryEntry {
-            id: r.get(0),
-            query: r.get(1),
-            connection_id: r.get(2),
-            connection_name: r.get(3),
-            database_name: r.get(4),
-            executed_at: r.get(5),
-            execution_time_ms: r.get(6),
-            row_count: r.get(7),
-            success: r.get::<i32, _>(8) != 0,
-            error_message: r.get(9),
-        }))
+        Ok(row.as_ref().map(row_to_entry))
     }
 
     /// List all history entries, optionally filtered by connection
@@ -161,52 +215,120 @@ impl QueryHistoryStorage {
         let limit = limit.unwrap_or(100);
 
         let entries = if let Some(conn_id) = connection_id {
-            sqlx::query(
-                r#"
-                SELECT id, query, connection_id, connection_name, database_name,
-                       datetime(executed_at) as executed_at, execution_time_ms,
-                       row_count, success, error_message
-                FROM query_history
-                WHERE connection_id = ?
-                ORDER BY executed_at DESC
-                LIMIT ?
-                "#,
-            )
+            sqlx::query(&format!(
+                "SELECT {} FROM query_history WHERE connection_id = ? ORDER BY query_history.last_run_at DESC LIMIT ?",
+                SELECT_COLUMNS
+            ))
             .bind(conn_id)
             .bind(limit)
             .fetch_all(&self.pool)
             .await?
         } else {
-            sqlx::query(
-                r#"
-                SELECT id, query, connection_id, connection_name, database_name,
-                       datetime(executed_at) as executed_at, execution_time_ms,
-                       row_count, success, error_message
-                FROM query_history
-                ORDER BY executed_at DESC
-                LIMIT ?
-                "#,
-            )
+            sqlx::query(&format!(
+                "SELECT {} FROM query_history ORDER BY query_history.last_run_at DESC LIMIT ?",
+                SELECT_COLUMNS
+            ))
             .bind(limit)
             .fetch_all(&self.pool)
             .await?
         };
 
-        Ok(entries
-            .iter()
-            .map(|r| QueryHistoryEntry {
-                id: r.get(0),
-                query: r.get(1),
-                connection_id: r.get(2),
-                connection_name: r.get(3),
-                database_name: r.get(4),
-                executed_at: r.get(5),
-                execution_time_ms: r.get(6),
-                row_count: r.get(7),
-                success: r.get::<i32, _>(8) != 0,
-                error_message: r.get(9),
-            })
-            .collect())
+        Ok(entries.iter().map(row_to_entry).collect())
+    }
+
+    /// Search history with full-text matching and filters, one page at a time
+    ///
+    /// Results are ordered by last run, newest first. Pages are keyed on
+    /// (`last_run_at`, `id`) rather than offsets, so entries added while
+    /// paging don't shift or repeat results.
+    pub async fn search(&self, search: &QueryHistorySearch) -> Result<QueryHistoryPage, AppError> {
+        let limit = search
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_PAGE_SIZE)
+            .clamp(1, MAX_SEARCH_PAGE_SIZE);
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM query_history WHERE 1 = 1", SELECT_COLUMNS));
+
+        if let Some(text) = search.text.as_deref().and_then(fts_query) {
+            builder
+                .push(" AND rowid IN (SELECT rowid FROM query_history_fts WHERE query_history_fts MATCH ")
+                .push_bind(text)
+                .push(")");
+        }
+        if let Some(connection_id) = &search.connection_id {
+            builder.push(" AND connection_id = ").push_bind(connection_id.clone());
+        }
+        if let Some(database_name) = &search.database_name {
+            builder.push(" AND database_name = ").push_bind(database_name.clone());
+        }
+        if let Some(success) = search.success {
+            builder.push(" AND success = ").push_bind(success);
+        }
+        // An entry covers its runs from executed_at to last_run_at
+        if let Some(after) = &search.executed_after {
+            let after = self.normalize_datetime(after).await?;
+            builder.push(" AND query_history.last_run_at >= ").push_bind(after);
+        }
+        if let Some(before) = &search.executed_before {
+            let before = self.normalize_datetime(before).await?;
+            builder.push(" AND query_history.executed_at < ").push_bind(before);
+        }
+        if let Some(min_time) = search.min_execution_time_ms {
+            builder.push(" AND execution_time_ms >= ").push_bind(min_time);
+        }
+        if let Some(pinned) = search.pinned {
+            builder.push(" AND pinned = ").push_bind(pinned);
+        }
+        if let Some(cursor) = &search.cursor {
+            let (last_run_at, id) = parse_cursor(cursor)?;
+            builder
+                .push(" AND (query_history.last_run_at < ")
+                .push_bind(last_run_at.clone())
+                .push(" OR (query_history.last_run_at = ")
+                .push_bind(last_run_at)
+                .push(" AND id < ")
+                .push_bind(id)
+                .push("))");
+        }
+
+        // One extra row tells us whether there's another page
+        builder
+            .push(" ORDER BY query_history.last_run_at DESC, id DESC LIMIT ")
+            .push_bind(limit + 1);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let mut entries: Vec<QueryHistoryEntry> = rows.iter().map(row_to_entry).collect();
+
+        let next_cursor = if entries.len() as i64 > limit {
+            entries.truncate(limit as usize);
+            entries.last().map(|e| format!("{}|{}", e.last_run_at, e.id))
+        } else {
+            None
+        };
+
+        Ok(QueryHistoryPage { entries, next_cursor })
+    }
+
+    /// Turn a user-supplied date/time into the format timestamps are stored in
+    async fn normalize_datetime(&self, value: &str) -> Result<String, AppError> {
+        let normalized: Option<String> = sqlx::query_scalar("SELECT datetime(?)")
+            .bind(value.trim())
+            .fetch_one(&self.pool)
+            .await?;
+        normalized.ok_or_else(|| AppError::Validation(format!("Invalid date/time: {}", value)))
+    }
+
+    /// Pin or unpin an entry - pinned entries survive cleanup
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> Result<QueryHistoryEntry, AppError> {
+        sqlx::query("UPDATE query_history SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.get(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("History entry not found: {}", id)))
     }
 
     /// Delete a single history entry
@@ -228,38 +350,353 @@ impl QueryHistoryStorage {
         Ok(())
     }
 
-    /// Cleanup old entries (30 days) and enforce max count (1000)
-    async fn cleanup(&self) -> Result<(), AppError> {
-        // Delete entries older than 30 days
-        sqlx::query(&format!(
-            "DELETE FROM query_history WHERE executed_at < datetime('now', '-{} days')",
-            HISTORY_RETENTION_DAYS
-        ))
+    /// Drop unpinned entries past their workspace's retention 🧹
+    ///
+    /// A connection can be in several workspaces; its entries follow the
+    /// most generous one. Entries of connections in no workspace use the
+    /// defaults. Returns how many entries were removed.
+    pub async fn cleanup(&self) -> Result<u64, AppError> {
+        // Entries not run within the retention period
+        let expired = sqlx::query(
+            r#"
+            DELETE FROM query_history
+            WHERE pinned = 0
+              AND last_run_at < datetime('now', '-' || COALESCE(
+                  (SELECT MAX(COALESCE(w.history_retention_days, ?))
+                   FROM workspace_connections wc
+                   JOIN workspaces w ON w.id = wc.workspace_id
+                   WHERE wc.connection_id = query_history.connection_id),
+                  ?) || ' days')
+            "#,
+        )
+        .bind(DEFAULT_HISTORY_RETENTION_DAYS)
+        .bind(DEFAULT_HISTORY_RETENTION_DAYS)
         .execute(&self.pool)
-        .await?;
+        .await?
+        .rows_affected();
 
-        // If still over limit, delete oldest entries
-        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM query_history")
-            .fetch_one(&self.pool)
-            .await?;
+        // Entries past the newest max_entries of every workspace they're in
+        let over_limit = sqlx::query(
+            r#"
+            WITH memberships AS (
+                SELECT h.id, h.last_run_at, h.rowid AS seq, wc.workspace_id AS grp,
+                       COALESCE(w.history_max_entries, ?) AS max_entries
+                FROM query_history h
+                JOIN workspace_connections wc ON wc.connection_id = h.connection_id
+                JOIN workspaces w ON w.id = wc.workspace_id
+                WHERE h.pinned = 0
+                UNION ALL
+                SELECT h.id, h.last_run_at, h.rowid AS seq, NULL AS grp, ? AS max_entries
+                FROM query_history h
+                WHERE h.pinned = 0
+                  AND NOT EXISTS (SELECT 1 FROM workspace_connections wc WHERE wc.connection_id = h.connection_id)
+            ),
+            ranked AS (
+                SELECT id, max_entries,
+                       ROW_NUMBER() OVER (PARTITION BY grp ORDER BY last_run_at DESC, seq DESC) AS position
+                FROM memberships
+            )
+            DELETE FROM query_history
+            WHERE id IN (
+                SELECT id FROM ranked
+                GROUP BY id
+                HAVING MIN(position - max_entries) > 0
+            )
+            "#,
+        )
+        .bind(DEFAULT_MAX_HISTORY_ENTRIES)
+        .bind(DEFAULT_MAX_HISTORY_ENTRIES)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
 
-        if count >= MAX_HISTORY_ENTRIES {
-            let to_delete = count - MAX_HISTORY_ENTRIES + 1; // +1 to make room for new entry
-            sqlx::query(
-                r#"
-                DELETE FROM query_history
-                WHERE id IN (
-                    SELECT id FROM query_history
-                    ORDER BY executed_at ASC
-                    LIMIT ?
-                )
-                "#,
+        Ok(expired + over_limit)
+    }
+}
+
+fn row_to_entry(r: &SqliteRow) -> QueryHistoryEntry {
+    QueryHistoryEntry {
+        id: r.get(0),
+        query: r.get(1),
+        connection_id: r.get(2),
+        connection_name: r.get(3),
+        database_name: r.get(4),
+        schema_name: r.get(13),
+        executed_at: r.get(5),
+        execution_time_ms: r.get(6),
+        row_count: r.get(7),
+        success: r.get::<i32, _>(8) != 0,
+        error_message: r.get(9),
+        run_count: r.get(10),
+        last_run_at: r.get(11),
+        pinned: r.get::<i32, _>(12) != 0,
+    }
+}
+
+/// Build an FTS5 query from free text: every word must appear, as a prefix
+///
+/// Words are quoted, so FTS syntax characters in the search (`*`, `"`,
+/// `-`, `:` ...) can't break the query - the tokenizer just skips them.
+/// Words made only of punctuation are dropped.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Split a page cursor back into its `last_run_at` and `id`
+fn parse_cursor(cursor: &str) -> Result<(String, String), AppError> {
+    cursor
+        .split_once('|')
+        .map(|(executed_at, id)| (executed_at.to_string(), id.to_string()))
+        .ok_or_else(|| AppError::Validation(format!("Invalid history cursor: {}", cursor)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{ConnectionStorage, HistoryRetention, WorkspaceConfig, WorkspaceStorage};
+    use tempfile::tempdir;
+
+    fn input(query: &str, database_name: &str, success: bool, execution_time_ms: i64) -> AddQueryHistoryInput {
+        input_for("conn", query, database_name, success, execution_time_ms)
+    }
+
+    fn input_for(
+        connection_id: &str,
+        query: &str,
+        database_name: &str,
+        success: bool,
+        execution_time_ms: i64,
+    ) -> AddQueryHistoryInput {
+        AddQueryHistoryInput {
+            query: query.to_string(),
+            connection_id: connection_id.to_string(),
+            connection_name: "Shop".to_string(),
+            database_name: Some(database_name.to_string()),
+            schema_name: None,
+            execution_time_ms: Some(execution_time_ms),
+            row_count: None,
+            success,
+            error_message: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_filters() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let history = QueryHistoryStorage::new(storage.get_pool());
+
+        let slow = history.add(&input("SELECT * FROM orders WHERE total > 100", "shop", true, 2500)).await.unwrap();
+        history.add(&input("SELECT * FROM orders_archive", "archive", true, 10)).await.unwrap();
+        history.add(&input("DELETE FROM customers", "shop", false, 5)).await.unwrap();
+        sqlx::query("UPDATE query_history SET executed_at = '2024-05-14 09:30:00', last_run_at = '2024-05-14 09:30:00' WHERE id = ?")
+            .bind(&slow.id)
+            .execute(&storage.get_pool())
+            .await
+            .unwrap();
+
+        let search = |search: QueryHistorySearch| {
+            let history = &history;
+            async move {
+                let mut queries: Vec<String> =
+                    history.search(&search).await.unwrap().entries.into_iter().map(|e| e.query).collect();
+                queries.sort();
+                queries
+            }
+        };
+
+        // Prefix matching: "order" finds both orders tables
+        let found = search(QueryHistorySearch {
+            text: Some("order".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found.len(), 2);
+
+        let found = search(QueryHistorySearch {
+            text: Some("orders total".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found, vec!["SELECT * FROM orders WHERE total > 100"]);
+
+        let found = search(QueryHistorySearch {
+            database_name: Some("shop".to_string()),
+            success: Some(false),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found, vec!["DELETE FROM customers"]);
+
+        let found = search(QueryHistorySearch {
+            executed_after: Some("2024-05-14".to_string()),
+            executed_before: Some("2024-05-15".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found, vec!["SELECT * FROM orders WHERE total > 100"]);
+
+        let found = search(QueryHistorySearch {
+            min_execution_time_ms: Some(1000),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found, vec!["SELECT * FROM orders WHERE total > 100"]);
+
+        // FTS syntax in the search text doesn't break the query
+        let found = search(QueryHistorySearch {
+            text: Some("\"orders* total\" - >".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(found, vec!["SELECT * FROM orders WHERE total > 100"]);
+
+        let invalid = history
+            .search(&QueryHistorySearch {
+                executed_after: Some("last tuesday".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(matches!(invalid, Err(AppError::Validation(_))));
+
+        // Deleted entries drop out of the index
+        history.delete(&slow.id).await.unwrap();
+        let found = search(QueryHistorySearch {
+            text: Some("total".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert!(found.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_pages_with_cursor() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let history = QueryHistoryStorage::new(storage.get_pool());
+
+        for i in 0..5 {
+            history.add(&input(&format!("SELECT {}", i), "shop", true, 1)).await.unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = history
+                .search(&QueryHistorySearch {
+                    cursor: cursor.take(),
+                    limit: Some(2),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert!(page.entries.len() <= 2);
+            seen.extend(page.entries.into_iter().map(|e| e.id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen.len(), 5);
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_queries_collapse() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let history = QueryHistoryStorage::new(storage.get_pool());
+
+        let first = history.add(&input("SELECT 1", "shop", true, 10)).await.unwrap();
+        let again = history.add(&input("SELECT 1", "shop", true, 20)).await.unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.run_count, 2);
+        assert_eq!(again.execution_time_ms, Some(20));
+
+        // Only consecutive runs with the same database and outcome collapse
+        let failed = history.add(&input("SELECT 1", "shop", false, 5)).await.unwrap();
+        assert_ne!(failed.id, first.id);
+        let other_db = history.add(&input("SELECT 1", "archive", false, 5)).await.unwrap();
+        assert_ne!(other_db.id, failed.id);
+        history.add(&input("SELECT 2", "shop", true, 5)).await.unwrap();
+        let later = history.add(&input("SELECT 1", "shop", true, 5)).await.unwrap();
+        assert_ne!(later.id, first.id);
+        assert_eq!(later.run_count, 1);
+
+        assert_eq!(history.list(None, None).await.unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_follows_workspace_retention_and_keeps_pinned() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let pool = storage.get_pool();
+        let history = QueryHistoryStorage::new(pool.clone());
+        let workspaces = WorkspaceStorage::new(pool.clone());
+
+        let workspace = workspaces
+            .create(&WorkspaceConfig {
+                name: "Production".to_string(),
+                icon: "database".to_string(),
+            })
+            .await
+            .unwrap();
+        workspaces.add_connection(&workspace.id, "prod").await.unwrap();
+        let retention = HistoryRetention {
+            retention_days: Some(7),
+            max_entries: Some(2),
+        };
+        let workspace = workspaces.set_history_retention(&workspace.id, &retention).await.unwrap();
+        assert_eq!(workspace.history_retention, retention);
+
+        let invalid = workspaces
+            .set_history_retention(
+                &workspace.id,
+                &HistoryRetention {
+                    retention_days: Some(0),
+                    max_entries: None,
+                },
             )
-            .bind(to_delete)
-            .execute(&self.pool)
-            .await?;
+            .await;
+        assert!(matches!(invalid, Err(AppError::Validation(_))));
+
+        let pinned = history.add(&input_for("prod", "SELECT 0", "shop", true, 1)).await.unwrap();
+        history.set_pinned(&pinned.id, true).await.unwrap();
+        let mut prod = Vec::new();
+        for i in 1..=4 {
+            prod.push(history.add(&input_for("prod", &format!("SELECT {}", i), "shop", true, 1)).await.unwrap());
         }
+        let dev = history.add(&input_for("dev", "SELECT 1", "shop", true, 1)).await.unwrap();
 
-        Ok(())
+        // 10 days old: past the workspace's 7 days, within the default 30
+        for id in [&prod[3].id, &dev.id, &pinned.id] {
+            sqlx::query("UPDATE query_history SET last_run_at = datetime('now', '-10 days') WHERE id = ?")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        // prod[3] expires, then prod[0] is past the newest 2
+        assert_eq!(history.cleanup().await.unwrap(), 2);
+
+        let mut remaining: Vec<String> = history.list(None, None).await.unwrap().into_iter().map(|e| e.id).collect();
+        remaining.sort();
+        let mut expected = vec![pinned.id.clone(), prod[1].id.clone(), prod[2].id.clone(), dev.id.clone()];
+        expected.sort();
+        assert_eq!(remaining, expected);
+
+        // Unpinned, it goes like the rest
+        history.set_pinned(&pinned.id, false).await.unwrap();
+        assert_eq!(history.cleanup().await.unwrap(), 1);
+        assert!(history.get(&pinned.id).await.unwrap().is_none());
     }
 }
@@ -58,40 +58,6 @@ impl SavedQueriesStorage {
         Self { pool }
     }
 
-    /// Initialize the saved_queries table schema
-    pub async fn initialize_schema(&self) -> Result<(), AppError> {
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS saved_queries (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                query TEXT NOT NULL,
-                description TEXT,
-                workspace_id TEXT,
-                connection_id TEXT,
-                database_name TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE SET NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create index on workspace_id for faster filtering
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_saved_queries_workspace
-            ON saved_queries(workspace_id)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
-    }
-
     /// Create a new saved query
     pub async fn create(&self, input: &CreateSavedQueryInput) -> Result<SavedQuery, AppError> {
         let id = Uuid::new_v4().to_string();
@@ -0,0 +1,336 @@
+//! Optional master password for saved credentials! 🔒✨
+//!
+//! By default saved passwords are encrypted with a key derived from the
+//! machine ID, so anyone using this machine can decrypt them. Turning on the
+//! master password switches to a key derived from a passphrase and a random
+//! per-install salt instead:
+//!
+//! - The app starts locked and `unlock` has to be called with the passphrase
+//! - It locks itself again after a configurable time without using credentials
+//! - Turning it on (or off) re-encrypts every stored password and SSH secret
+//!
+//! Only the salt and a verifier (a known text encrypted with the key) are
+//! stored - never the passphrase or the key itself~ 🌟
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Row, Sqlite};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::storage::encryption::Encryptor;
+
+/// Encrypted with the master key and stored, to check a passphrase on unlock
+const VERIFIER_TEXT: &str = "anko-master-password-verifier-v1";
+/// Shortest master password accepted
+const MIN_MASTER_PASSWORD_LEN: usize = 8;
+
+/// Whether a master password is set and whether credentials are unlocked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MasterPasswordStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    /// Lock again after this many idle minutes (`None` = only on restart)
+    pub auto_lock_minutes: Option<u32>,
+}
+
+/// In-memory key state
+struct VaultState {
+    /// The key in use, or `None` while locked
+    encryptor: Option<Arc<Encryptor>>,
+    /// True if the key comes from a master password
+    master_password: bool,
+    auto_lock_minutes: Option<u32>,
+    /// Last time credentials were encrypted or decrypted
+    last_used: Instant,
+}
+
+/// Hands out the credential encryptor, locking it behind the master password if one is set 🔐
+pub struct CredentialVault {
+    pool: Pool<Sqlite>,
+    state: Mutex<VaultState>,
+}
+
+impl CredentialVault {
+    /// Open the vault, starting locked if a master password is set.
+    ///
+    /// The database must already be migrated.
+    ///
+    /// # Errors
+    /// Returns `AppError` if the settings can't be read,
+    /// or the machine key can't be derived.
+    pub async fn open(pool: Pool<Sqlite>) -> Result<Self, AppError> {
+        let row = sqlx::query("SELECT auto_lock_minutes FROM master_password WHERE id = 1")
+            .fetch_optional(&pool)
+            .await?;
+
+        let state = match row {
+            Some(row) => VaultState {
+                encryptor: None,
+                master_password: true,
+                auto_lock_minutes: row.get::<Option<i64>, _>(0).map(|m| m as u32),
+                last_used: Instant::now(),
+            },
+            None => VaultState {
+                encryptor: Some(Arc::new(Encryptor::new()?)),
+                master_password: false,
+                auto_lock_minutes: None,
+                last_used: Instant::now(),
+            },
+        };
+
+        Ok(Self {
+            pool,
+            state: Mutex::new(state),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, VaultState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The encryptor for credentials, counting as activity for auto-lock.
+    ///
+    /// # Errors
+    /// Returns `AppError::CredentialsLocked` while locked.
+    pub fn encryptor(&self) -> Result<Arc<Encryptor>, AppError> {
+        let mut state = self.state();
+        let encryptor = state.encryptor.clone().ok_or(AppError::CredentialsLocked)?;
+        state.last_used = Instant::now();
+        Ok(encryptor)
+    }
+
+    pub fn status(&self) -> MasterPasswordStatus {
+        let state = self.state();
+        MasterPasswordStatus {
+            enabled: state.master_password,
+            locked: state.encryptor.is_none(),
+            auto_lock_minutes: state.auto_lock_minutes,
+        }
+    }
+
+    /// Check the passphrase against the stored verifier and return its encryptor
+    async fn verify(&self, passphrase: &str) -> Result<Encryptor, AppError> {
+        let row = sqlx::query("SELECT salt, verifier FROM master_password WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::Validation("No master password is set".to_string()))?;
+
+        let salt: String = row.get(0);
+        let verifier: Vec<u8> = row.get(1);
+        let encryptor = Encryptor::with_passphrase(passphrase, &salt)?;
+        match encryptor.decrypt(&verifier) {
+            Ok(text) if text == VERIFIER_TEXT => Ok(encryptor),
+            _ => Err(AppError::Validation("Wrong master password".to_string())),
+        }
+    }
+
+    /// Unlock saved credentials with the master password 🔓
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if no master password is set or it's wrong.
+    pub async fn unlock(&self, passphrase: &str) -> Result<(), AppError> {
+        let encryptor = self.verify(passphrase).await?;
+        let mut state = self.state();
+        state.encryptor = Some(Arc::new(encryptor));
+        state.last_used = Instant::now();
+        Ok(())
+    }
+
+    /// Forget the key until the next `unlock` (no-op without a master password)
+    pub fn lock(&self) {
+        let mut state = self.state();
+        if state.master_password {
+            state.encryptor = None;
+        }
+    }
+
+    /// Lock if credentials haven't been used for the auto-lock time.
+    ///
+    /// Called periodically by a background task. Returns true if it locked.
+    pub fn lock_if_idle(&self) -> bool {
+        let mut state = self.state();
+        let Some(minutes) = state.auto_lock_minutes else {
+            return false;
+        };
+        let idle = state.last_used.elapsed() >= Duration::from_secs(u64::from(minutes) * 60);
+        if state.master_password && state.encryptor.is_some() && idle {
+            state.encryptor = None;
+            return true;
+        }
+        false
+    }
+
+    /// Turn on the master password, re-encrypting every stored credential 🔑
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if a master password is already set or
+    /// the passphrase is too short. Nothing changes if re-encryption fails.
+    pub async fn enable(&self, passphrase: &str, auto_lock_minutes: Option<u32>) -> Result<(), AppError> {
+        if self.status().enabled {
+            return Err(AppError::Validation("A master password is already set".to_string()));
+        }
+        if passphrase.chars().count() < MIN_MASTER_PASSWORD_LEN {
+            return Err(AppError::Validation(format!(
+                "The master password needs at least {} characters",
+                MIN_MASTER_PASSWORD_LEN
+            )));
+        }
+
+        let current = self.encryptor()?;
+        let salt = Encryptor::generate_salt()?;
+        let master = Encryptor::with_passphrase(passphrase, &salt)?;
+
+        let mut tx = self.pool.begin().await?;
+        reencrypt_credentials(&mut tx, &current, &master).await?;
+        sqlx::query("INSERT INTO master_password (id, salt, verifier, auto_lock_minutes) VALUES (1, ?, ?, ?)")
+            .bind(&salt)
+            .bind(master.encrypt(VERIFIER_TEXT)?)
+            .bind(auto_lock_minutes.map(i64::from))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let mut state = self.state();
+        state.encryptor = Some(Arc::new(master));
+        state.master_password = true;
+        state.auto_lock_minutes = auto_lock_minutes;
+        state.last_used = Instant::now();
+        Ok(())
+    }
+
+    /// Turn the master password off, going back to the machine key
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if no master password is set or it's wrong.
+    pub async fn disable(&self, passphrase: &str) -> Result<(), AppError> {
+        let master = self.verify(passphrase).await?;
+        let machine = Encryptor::new()?;
+
+        let mut tx = self.pool.begin().await?;
+        reencrypt_credentials(&mut tx, &master, &machine).await?;
+        sqlx::query("DELETE FROM master_password").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        let mut state = self.state();
+        state.encryptor = Some(Arc::new(machine));
+        state.master_password = false;
+        state.auto_lock_minutes = None;
+        state.last_used = Instant::now();
+        Ok(())
+    }
+
+    /// Change how long the app may sit idle before locking
+    pub async fn set_auto_lock(&self, auto_lock_minutes: Option<u32>) -> Result<(), AppError> {
+        if !self.status().enabled {
+            return Err(AppError::Validation("No master password is set".to_string()));
+        }
+        sqlx::query("UPDATE master_password SET auto_lock_minutes = ? WHERE id = 1")
+            .bind(auto_lock_minutes.map(i64::from))
+            .execute(&self.pool)
+            .await?;
+        self.state().auto_lock_minutes = auto_lock_minutes;
+        Ok(())
+    }
+}
+
+/// Decrypt every password and SSH secret stored in the rows with `from` and encrypt it with `to`
+async fn reencrypt_credentials(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    from: &Encryptor,
+    to: &Encryptor,
+) -> Result<(), AppError> {
+    // Keyring and prompt connections keep nothing in their rows
+    let rows = sqlx::query(
+        "SELECT id, encrypted_password, encrypted_ssh_secret FROM connections \
+         WHERE credential_backend IS NULL OR credential_backend = 'encrypted'",
+    )
+        .fetch_all(&mut **tx)
+        .await?;
+
+    for row in rows {
+        let id: String = row.get(0);
+        let password = from.decrypt(&row.get::<Vec<u8>, _>(1))?;
+        let ssh_secret = row
+            .get::<Option<Vec<u8>>, _>(2)
+            .map(|encrypted| from.decrypt(&encrypted))
+            .transpose()?;
+
+        sqlx::query("UPDATE connections SET encrypted_password = ?, encrypted_ssh_secret = ? WHERE id = ?")
+            .bind(to.encrypt(&password)?)
+            .bind(ssh_secret.map(|secret| to.encrypt(&secret)).transpose()?)
+            .bind(&id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connector::{ConnectionConfig, DatabaseDriver, QueryLimits};
+    use crate::db::tls::TlsConfig;
+    use crate::storage::ConnectionStorage;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_master_password_lifecycle() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let saved = storage
+            .save(&ConnectionConfig {
+                name: "Prod".to_string(),
+                host: "db.internal".to_string(),
+                port: 5432,
+                username: "app".to_string(),
+                password: "prod_password".to_string(),
+                database: None,
+                driver: DatabaseDriver::PostgreSQL,
+                file_path: None,
+                limits: QueryLimits::default(),
+                ssh_tunnel: None,
+                tls: TlsConfig::default(),
+                params: BTreeMap::new(),
+                credential_backend: Default::default(),
+                record_history: true,
+            })
+            .await
+            .unwrap();
+
+        storage.vault().enable("master password", Some(15)).await.unwrap();
+        storage.vault().lock();
+        assert!(matches!(
+            storage.decrypt_password(&saved.encrypted_password),
+            Err(AppError::CredentialsLocked)
+        ));
+
+        // A fresh start is locked too, and the old blob no longer decrypts
+        let reopened = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        assert!(reopened.vault().status().locked);
+        assert!(reopened.vault().unlock("wrong password").await.is_err());
+        reopened.vault().unlock("master password").await.unwrap();
+        let stored = reopened.get(&saved.id).await.unwrap().unwrap();
+        assert!(reopened.decrypt_password(&saved.encrypted_password).is_err());
+        assert_eq!(reopened.decrypt_password(&stored.encrypted_password).unwrap(), "prod_password");
+
+        reopened.vault().disable("master password").await.unwrap();
+        let machine_keyed = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        let stored = machine_keyed.get(&saved.id).await.unwrap().unwrap();
+        assert!(!machine_keyed.vault().status().enabled);
+        assert_eq!(machine_keyed.decrypt_password(&stored.encrypted_password).unwrap(), "prod_password");
+    }
+
+    #[tokio::test]
+    async fn test_lock_if_idle_needs_master_password() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ConnectionStorage::new(temp_dir.path()).await.unwrap();
+        assert!(!storage.vault().lock_if_idle());
+
+        storage.vault().enable("master password", Some(0)).await.unwrap();
+        assert!(storage.vault().lock_if_idle());
+        assert!(storage.vault().status().locked);
+    }
+}
@@ -4,8 +4,6 @@ use uuid::Uuid;
 
 use crate::error::AppError;
 
-const DEFAULT_WORKSPACE_ID: &str = "default";
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workspace {
     pub id: String,
@@ -13,10 +11,24 @@ pub struct Workspace {
     pub icon: String,
     pub is_default: bool,
     pub connection_ids: Vec<String>,
+    #[serde(default)]
+    pub history_retention: HistoryRetention,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// How much query history to keep for a workspace's connections
+///
+/// `None` falls back to the app defaults (30 days, 1000 entries). Pinned
+/// entries are always kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRetention {
+    /// Drop entries not run for this many days
+    pub retention_days: Option<i64>,
+    /// Keep at most this many entries across the workspace's connections
+    pub max_entries: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceConfig {
     pub name: String,
@@ -32,65 +44,6 @@ impl WorkspaceStorage {
         Self { pool }
     }
 
-    pub async fn initialize_schema(&self) -> Result<(), AppError> {
-        // Create workspaces table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS workspaces (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                icon TEXT NOT NULL DEFAULT 'database',
-                is_default INTEGER NOT NULL DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create workspace_connections junction table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS workspace_connections (
-                workspace_id TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
-                added_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (workspace_id, connection_id),
-                FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Ensure default workspace exists
-        self.ensure_default_workspace().await?;
-
-        Ok(())
-    }
-
-    async fn ensure_default_workspace(&self) -> Result<(), AppError> {
-        let exists = sqlx::query("SELECT id FROM workspaces WHERE id = ?")
-            .bind(DEFAULT_WORKSPACE_ID)
-            .fetch_optional(&self.pool)
-            .await?;
-
-        if exists.is_none() {
-            sqlx::query(
-                r#"
-                INSERT INTO workspaces (id, name, icon, is_default)
-                VALUES (?, 'Default', 'database', 1)
-                "#,
-            )
-            .bind(DEFAULT_WORKSPACE_ID)
-            .execute(&self.pool)
-            .await?;
-        }
-
-        Ok(())
-    }
-
     pub async fn create(&self, config: &WorkspaceConfig) -> Result<Workspace, AppError> {
         let id = Uuid::new_v4().to_string();
 
@@ -126,6 +79,31 @@ impl WorkspaceStorage {
         self.get(id).await?.ok_or_else(|| AppError::NotFound("Workspace not found".into()))
     }
 
+    /// Set how much query history is kept for the workspace's connections
+    pub async fn set_history_retention(&self, id: &str, retention: &HistoryRetention) -> Result<Workspace, AppError> {
+        if retention.retention_days.is_some_and(|days| days < 1) {
+            return Err(AppError::Validation("History retention must be at least 1 day".into()));
+        }
+        if retention.max_entries.is_some_and(|entries| entries < 1) {
+            return Err(AppError::Validation("History must keep at least 1 entry".into()));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE workspaces
+            SET history_retention_days = ?, history_max_entries = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(retention.retention_days)
+        .bind(retention.max_entries)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(id).await?.ok_or_else(|| AppError::NotFound("Workspace not found".into()))
+    }
+
     pub async fn delete(&self, id: &str) -> Result<(), AppError> {
         // Check if it's the default workspace
         let workspace = self.get(id).await?;
@@ -147,7 +125,8 @@ impl WorkspaceStorage {
     pub async fn list(&self) -> Result<Vec<Workspace>, AppError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, name, icon, is_default, created_at, updated_at
+            SELECT id, name, icon, is_default, created_at, updated_at,
+                   history_retention_days, history_max_entries
             FROM workspaces
             ORDER BY is_default DESC, name ASC
             "#,
@@ -166,6 +145,10 @@ impl WorkspaceStorage {
                 icon: row.get(2),
                 is_default: row.get::<i32, _>(3) == 1,
                 connection_ids,
+                history_retention: HistoryRetention {
+                    retention_days: row.get(6),
+                    max_entries: row.get(7),
+                },
                 created_at: row.get(4),
                 updated_at: row.get(5),
             });
@@ -177,7 +160,8 @@ impl WorkspaceStorage {
     pub async fn get(&self, id: &str) -> Result<Option<Workspace>, AppError> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, icon, is_default, created_at, updated_at
+            SELECT id, name, icon, is_default, created_at, updated_at,
+                   history_retention_days, history_max_entries
             FROM workspaces
             WHERE id = ?
             "#,
@@ -197,6 +181,10 @@ impl WorkspaceStorage {
                     icon: row.get(2),
                     is_default: row.get::<i32, _>(3) == 1,
                     connection_ids,
+                    history_retention: HistoryRetention {
+                        retention_days: row.get(6),
+                        max_entries: row.get(7),
+                    },
                     created_at: row.get(4),
                     updated_at: row.get(5),
                 }))